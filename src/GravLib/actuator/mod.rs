@@ -7,4 +7,4 @@ pub mod motor_group;
 
 // pub use smartmotor::SmartMotor;
 
-pub use motor_group::MotorGroup; 
\ No newline at end of file
+pub use motor_group::{MotorGroup, MotorGroupError}; 
\ No newline at end of file
@@ -3,76 +3,379 @@ extern crate alloc;
 
 use alloc::boxed::Box;
 use alloc::vec::Vec;
-use vexide::devices::adi::motor;
-use core::time::Duration;
-use spin::Mutex;
-use vexide::prelude::{BrakeMode, Gearset, Motor};
-use vexide::task;
+use alloc::vec;
+use core::fmt;
+#[cfg(target_vendor = "vex")]
+use vexide::io::println;
+#[cfg(target_vendor = "vex")]
+use vexide::prelude::Motor;
 use libm::roundf;
 
+use crate::GravLib::hardware::{Actuator, ActuatorError, BrakeMode, Gearset};
+
+/// Every motor in a group that failed, by its index in the group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MotorGroupError {
+    pub failures: Vec<(usize, ActuatorError)>,
+}
+
+impl MotorGroupError {
+    /// Whether the motor at `index` is one of the failures.
+    pub fn failed(&self, index: usize) -> bool {
+        self.failures.iter().any(|(i, _)| *i == index)
+    }
+}
+
+impl fmt::Display for MotorGroupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (n, (index, error)) in self.failures.iter().enumerate() {
+            if n > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "motor {}: {}", index, error)?;
+        }
+        Ok(())
+    }
+}
+
 pub struct MotorGroup {
-    motors: Vec<Motor>,
+    motors: Vec<Box<dyn Actuator>>,
+    /// Output revolutions per motor revolution, e.g. 0.75 for a 36:48
+    /// reduction to the wheels.
+    ratio: f64,
+    /// Names the group in logged failures.
+    name: &'static str,
+    /// Which motors failed their last command. A failure is logged when a
+    /// motor starts failing, not on every command after.
+    failing: Vec<bool>,
 }
 
 impl MotorGroup {
+    #[cfg(target_vendor = "vex")]
     pub fn new(motors: Vec<Motor>) -> Self {
-        Self { motors }
+        Self::from_actuators(
+            motors
+                .into_iter()
+                .map(|m| Box::new(m) as Box<dyn Actuator>)
+                .collect(),
+        )
     }
 
-    pub fn move_voltage(&mut self, voltage: f64) {
-        for motor in &mut self.motors {
-            let _ = motor.set_voltage(voltage); 
+    /// Builds a group from any motor implementation, e.g. `sim::SimMotor`.
+    pub fn from_actuators(motors: Vec<Box<dyn Actuator>>) -> Self {
+        let failing = vec![false; motors.len()];
+        Self {
+            motors,
+            ratio: 1.0,
+            name: "motor group",
+            failing,
         }
     }
 
-    // @dev_note: set_velocity method is built in PID by VEXIDE devs.
-    fn move_velocity(&mut self, velocity_percentage: f64) {
-        // Calculate velocity as percentage of max velocity
+    /// Scales `position` and `velocity` to the output of a gear train,
+    /// `ratio` output revolutions per motor revolution.
+    pub fn with_ratio(mut self, ratio: f64) -> Self {
+        self.ratio = ratio;
+        self
+    }
+
+    /// Names the group in logged failures, e.g. "left drive".
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.motors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.motors.is_empty()
+    }
+
+    /// Runs `command` on every motor, carrying on past failures so one bad
+    /// motor can't stop the rest. Logs each motor that starts failing, so a
+    /// caller that can't act on the error doesn't hide it.
+    fn command(
+        &mut self,
+        mut command: impl FnMut(&mut dyn Actuator) -> Result<(), ActuatorError>,
+    ) -> Result<(), MotorGroupError> {
+        let mut failures = Vec::new();
+        for (i, motor) in self.motors.iter_mut().enumerate() {
+            let result = command(motor.as_mut());
+            if let Err(e) = result {
+                if !self.failing[i] {
+                    println!("{} motor {} failed: {}", self.name, i, e);
+                }
+                failures.push((i, e));
+            }
+            self.failing[i] = result.is_err();
+        }
 
-        for motor in self.motors.iter_mut() {
-            let gearset = motor.gearset().unwrap();
+        if failures.is_empty() { Ok(()) } else { Err(MotorGroupError { failures }) }
+    }
+
+    /// Reads every motor, failing if any of them can't be read.
+    fn read<T>(
+        &self,
+        read: impl Fn(&dyn Actuator) -> Result<T, ActuatorError>,
+    ) -> Result<Vec<T>, MotorGroupError> {
+        let mut values = Vec::with_capacity(self.motors.len());
+        let mut failures = Vec::new();
+        for (i, motor) in self.motors.iter().enumerate() {
+            match read(motor.as_ref()) {
+                Ok(value) => values.push(value),
+                Err(e) => failures.push((i, e)),
+            }
+        }
+
+        if failures.is_empty() { Ok(values) } else { Err(MotorGroupError { failures }) }
+    }
+
+    fn average(&self, read: impl Fn(&dyn Actuator) -> Result<f64, ActuatorError>) -> Result<f64, MotorGroupError> {
+        let values = self.read(read)?;
+        if values.is_empty() {
+            return Ok(0.0);
+        }
+        Ok(values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    pub fn move_voltage(&mut self, voltage: f64) -> Result<(), MotorGroupError> {
+        self.command(|motor| motor.set_voltage(voltage))
+    }
 
-            let max_rpm = match gearset {
+    // @dev_note: set_velocity method is built in PID by VEXIDE devs.
+    /// Runs every motor's velocity controller at a percentage of its
+    /// cartridge's top speed.
+    pub fn move_velocity(&mut self, velocity_percentage: f64) -> Result<(), MotorGroupError> {
+        self.command(|motor| {
+            let max_rpm = match motor.gearset()? {
                 Gearset::Red   => 100,
                 Gearset::Green => 200,
                 Gearset::Blue  => 600,
             };
 
             // Convert percentages to rpm
-            let velocity_raw = 
+            let velocity_raw =
                 (velocity_percentage as f32 / 100.0)
                 * (max_rpm as f32);
 
-            let velocity = roundf(velocity_raw) as i32;
-            let _ = motor.set_velocity(velocity);
-        }
+            motor.set_velocity(roundf(velocity_raw) as i32)
+        })
+    }
+
+    pub fn brake(&mut self, mode: BrakeMode) -> Result<(), MotorGroupError> {
+        self.command(|motor| motor.brake(mode))
+    }
+
+    /// Zeroes every motor's position.
+    pub fn tare_position(&mut self) -> Result<(), MotorGroupError> {
+        self.command(|motor| motor.reset_position())
+    }
+
+    /// Average applied voltage.
+    pub fn voltage(&self) -> Result<f64, MotorGroupError> {
+        self.average(|motor| motor.voltage())
+    }
+
+    /// Average position in degrees at the output of the gear ratio.
+    pub fn position(&self) -> Result<f64, MotorGroupError> {
+        Ok(self.average(|motor| motor.position())? * self.ratio)
+    }
+
+    /// Average speed in rpm at the output of the gear ratio.
+    pub fn velocity(&self) -> Result<f64, MotorGroupError> {
+        Ok(self.average(|motor| motor.velocity())? * self.ratio)
+    }
+
+    /// Total current draw of the group, in amps.
+    pub fn current(&self) -> Result<f64, MotorGroupError> {
+        Ok(self.read(|motor| motor.current())?.iter().sum())
     }
 
-    fn voltage(&self) -> f64 {
-        let mut total = 0.0;
+    /// Temperature of the hottest motor, in °C.
+    pub fn temperature(&self) -> Result<f64, MotorGroupError> {
+        Ok(self
+            .read(|motor| motor.temperature())?
+            .into_iter()
+            .fold(0.0, f64::max))
+    }
+
+    /// Average efficiency, in percent.
+    pub fn efficiency(&self) -> Result<f64, MotorGroupError> {
+        self.average(|motor| motor.efficiency())
+    }
+
+    /// `Ok` if every motor is connected and healthy, otherwise which ones
+    /// aren't and why.
+    pub fn check(&self) -> Result<(), MotorGroupError> {
+        self.read(|motor| motor.faults()).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use spin::Mutex;
+
+    /// A motor with fixed readings that fails every command while
+    /// `unplugged` is set.
+    struct FlakyMotor {
+        unplugged: Arc<Mutex<bool>>,
+        volts: Arc<Mutex<f64>>,
+        position: f64,
+        current: f64,
+        temperature: f64,
+        fault: Option<ActuatorError>,
+    }
 
-        for motor in &self.motors {
-            if let Ok(voltage) = motor.voltage() {
-                total += voltage;
+    impl FlakyMotor {
+        fn new(position: f64, current: f64, temperature: f64) -> Self {
+            Self {
+                unplugged: Arc::new(Mutex::new(false)),
+                volts: Arc::new(Mutex::new(0.0)),
+                position,
+                current,
+                temperature,
+                fault: None,
             }
         }
-        total / self.motors.len() as f64
     }
-    
-    fn position(&self) -> f64 {
-        let mut total = 0.0;
-        for motor in &self.motors {
-            if let Ok(angle) = motor.position() {
-                total += angle.as_radians();
+
+    fn group(motors: Vec<FlakyMotor>) -> MotorGroup {
+        MotorGroup::from_actuators(motors.into_iter().map(|m| Box::new(m) as Box<dyn Actuator>).collect())
+    }
+
+    impl Actuator for FlakyMotor {
+        fn set_voltage(&mut self, volts: f64) -> Result<(), ActuatorError> {
+            if *self.unplugged.lock() {
+                return Err(ActuatorError::Disconnected);
             }
+            *self.volts.lock() = volts;
+            Ok(())
+        }
+        fn set_velocity(&mut self, _rpm: i32) -> Result<(), ActuatorError> {
+            Ok(())
+        }
+        fn brake(&mut self, _mode: BrakeMode) -> Result<(), ActuatorError> {
+            Ok(())
+        }
+        fn voltage(&self) -> Result<f64, ActuatorError> {
+            Ok(*self.volts.lock())
+        }
+        fn position(&self) -> Result<f64, ActuatorError> {
+            Ok(self.position)
+        }
+        fn reset_position(&mut self) -> Result<(), ActuatorError> {
+            if *self.unplugged.lock() {
+                return Err(ActuatorError::Disconnected);
+            }
+            self.position = 0.0;
+            Ok(())
+        }
+        fn velocity(&self) -> Result<f64, ActuatorError> {
+            Ok(0.0)
+        }
+        fn current(&self) -> Result<f64, ActuatorError> {
+            Ok(self.current)
+        }
+        fn temperature(&self) -> Result<f64, ActuatorError> {
+            Ok(self.temperature)
+        }
+        fn efficiency(&self) -> Result<f64, ActuatorError> {
+            Ok(100.0)
+        }
+        fn faults(&self) -> Result<(), ActuatorError> {
+            self.fault.map_or(Ok(()), Err)
+        }
+        fn gearset(&self) -> Result<Gearset, ActuatorError> {
+            Ok(Gearset::Blue)
         }
-        total
     }
-    
 
-    fn brake(&mut self, mode: BrakeMode) {
-        for motor in self.motors.iter_mut() {
-            let _ = motor.brake(mode);
-        }
+    #[test]
+    fn failing_motor_is_reported_and_the_rest_still_drive() {
+        let unplugged = Arc::new(Mutex::new(false));
+        let volts = [Arc::new(Mutex::new(0.0)), Arc::new(Mutex::new(0.0))];
+        let healthy = FlakyMotor {
+            volts: Arc::clone(&volts[0]),
+            ..FlakyMotor::new(0.0, 0.0, 25.0)
+        };
+        let flaky = FlakyMotor {
+            unplugged: Arc::clone(&unplugged),
+            volts: Arc::clone(&volts[1]),
+            ..FlakyMotor::new(0.0, 0.0, 25.0)
+        };
+        let mut group = group(vec![healthy, flaky]);
+
+        *unplugged.lock() = true;
+        let error = group.move_voltage(6.0).unwrap_err();
+        assert_eq!(error.failures, vec![(1, ActuatorError::Disconnected)]);
+        assert_eq!(*volts[0].lock(), 6.0);
+        assert_eq!(group.failing, vec![false, true]);
+
+        // Plugging it back in clears it, so a later failure is logged again
+        *unplugged.lock() = false;
+        group.move_voltage(3.0).unwrap();
+        assert_eq!(*volts[1].lock(), 3.0);
+        assert_eq!(group.failing, vec![false, false]);
+    }
+
+    #[test]
+    fn position_is_the_average_scaled_by_the_ratio() {
+        let motors = || vec![FlakyMotor::new(90.0, 0.0, 25.0), FlakyMotor::new(270.0, 0.0, 25.0)];
+        assert_eq!(group(motors()).position(), Ok(180.0));
+        // 36:48 to the wheels
+        assert_eq!(group(motors()).with_ratio(0.75).position(), Ok(135.0));
+        assert_eq!(group(Vec::new()).position(), Ok(0.0));
+    }
+
+    #[test]
+    fn temperature_is_the_hottest_motor() {
+        let group = group(vec![
+            FlakyMotor::new(0.0, 0.0, 30.0),
+            FlakyMotor::new(0.0, 0.0, 55.0),
+            FlakyMotor::new(0.0, 0.0, 40.0),
+        ]);
+        assert_eq!(group.temperature(), Ok(55.0));
+    }
+
+    #[test]
+    fn current_is_the_total_draw() {
+        let group = group(vec![FlakyMotor::new(0.0, 1.5, 25.0), FlakyMotor::new(0.0, 2.0, 25.0)]);
+        assert_eq!(group.current(), Ok(3.5));
+    }
+
+    #[test]
+    fn check_reports_every_faulty_motor() {
+        let motors = vec![
+            FlakyMotor { fault: Some(ActuatorError::OverTemperature), ..FlakyMotor::new(0.0, 0.0, 25.0) },
+            FlakyMotor::new(0.0, 0.0, 25.0),
+            FlakyMotor { fault: Some(ActuatorError::Disconnected), ..FlakyMotor::new(0.0, 0.0, 25.0) },
+        ];
+        let error = group(motors).check().unwrap_err();
+        assert_eq!(
+            error.failures,
+            vec![(0, ActuatorError::OverTemperature), (2, ActuatorError::Disconnected)]
+        );
+        assert!(error.failed(2) && !error.failed(1));
+        assert_eq!(error.to_string(), "motor 0: over temperature, motor 2: disconnected");
+
+        assert_eq!(group(vec![FlakyMotor::new(0.0, 0.0, 25.0)]).check(), Ok(()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn tare_zeroes_every_motor_it_can_reach() {
+        let unplugged = Arc::new(Mutex::new(true));
+        let flaky = FlakyMotor { unplugged: Arc::clone(&unplugged), ..FlakyMotor::new(120.0, 0.0, 25.0) };
+        let mut group = group(vec![FlakyMotor::new(80.0, 0.0, 25.0), flaky]);
+
+        assert_eq!(group.tare_position().unwrap_err().failures, vec![(1, ActuatorError::Disconnected)]);
+        assert_eq!(group.position(), Ok(60.0));
+
+        *unplugged.lock() = false;
+        group.tare_position().unwrap();
+        assert_eq!(group.position(), Ok(0.0));
+    }
+}
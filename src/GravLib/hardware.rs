@@ -0,0 +1,242 @@
+// hardware.rs
+//
+// Small traits the library reads its inputs through. The vexide devices
+// implement them for use on the Brain; `sim` and the test fakes implement them
+// so the same code can run on a host.
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::{fmt, future::Future, pin::Pin, time::Duration};
+
+#[cfg(target_vendor = "vex")]
+use vexide::{
+    devices::smart::{InertialSensor, RotationSensor},
+    prelude::Motor,
+    time::Instant,
+};
+#[cfg(not(target_vendor = "vex"))]
+use std::time::Instant;
+
+#[cfg(target_vendor = "vex")]
+pub use vexide::prelude::{BrakeMode, Gearset};
+
+/// Stand-in for vexide's `BrakeMode` on hosts, which don't have vexide.
+#[cfg(not(target_vendor = "vex"))]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BrakeMode {
+    Coast,
+    Brake,
+    Hold,
+}
+
+/// Stand-in for vexide's `Gearset` on hosts, which don't have vexide.
+#[cfg(not(target_vendor = "vex"))]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Gearset {
+    Red,
+    Green,
+    Blue,
+}
+
+/// A source of shaft angle, e.g. the rotation sensor on a tracking wheel.
+pub trait Encoder {
+    /// Total angle turned since the last reset, in degrees.
+    /// Returns `None` if the device could not be read.
+    fn angle(&self) -> Option<f64>;
+
+    /// Zeroes the accumulated angle.
+    fn reset(&mut self);
+}
+
+/// A source of absolute robot heading, e.g. the IMU.
+pub trait HeadingSource {
+    /// Heading in degrees, clockwise positive, wrapped to [0, 360).
+    /// Returns `None` if the device could not be read.
+    fn heading(&self) -> Option<f64>;
+
+    /// Calibrates the device. Completes once the heading is usable.
+    fn calibrate(&mut self) -> Pin<Box<dyn Future<Output = ()> + '_>>;
+}
+
+/// Why a motor couldn't be read, commanded, or is unhealthy.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ActuatorError {
+    /// The motor didn't respond, usually because it is unplugged.
+    Disconnected,
+    /// The motor is hot enough that its firmware is limiting power.
+    OverTemperature,
+    /// The motor is at its current limit, e.g. stalled.
+    OverCurrent,
+    /// The motor's H-bridge reported a fault.
+    DriverFault,
+}
+
+impl fmt::Display for ActuatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActuatorError::Disconnected => write!(f, "disconnected"),
+            ActuatorError::OverTemperature => write!(f, "over temperature"),
+            ActuatorError::OverCurrent => write!(f, "over current"),
+            ActuatorError::DriverFault => write!(f, "driver fault"),
+        }
+    }
+}
+
+/// A single smart motor, e.g. one member of a `MotorGroup`.
+pub trait Actuator {
+    /// Drives the motor open-loop at `volts`, from -12.0 to 12.0.
+    fn set_voltage(&mut self, volts: f64) -> Result<(), ActuatorError>;
+
+    /// Runs the motor's internal velocity controller at `rpm`.
+    fn set_velocity(&mut self, rpm: i32) -> Result<(), ActuatorError>;
+
+    fn brake(&mut self, mode: BrakeMode) -> Result<(), ActuatorError>;
+
+    /// Voltage currently applied to the motor.
+    fn voltage(&self) -> Result<f64, ActuatorError>;
+
+    /// Output shaft position in degrees.
+    fn position(&self) -> Result<f64, ActuatorError>;
+
+    /// Zeroes the output shaft position.
+    fn reset_position(&mut self) -> Result<(), ActuatorError>;
+
+    /// Output shaft speed in rpm.
+    fn velocity(&self) -> Result<f64, ActuatorError>;
+
+    /// Current draw in amps.
+    fn current(&self) -> Result<f64, ActuatorError>;
+
+    /// Motor temperature in °C.
+    fn temperature(&self) -> Result<f64, ActuatorError>;
+
+    /// Mechanical power out as a percentage of electrical power in.
+    fn efficiency(&self) -> Result<f64, ActuatorError>;
+
+    /// `Ok` if the motor reports no faults, otherwise the first one found.
+    fn faults(&self) -> Result<(), ActuatorError>;
+
+    fn gearset(&self) -> Result<Gearset, ActuatorError>;
+}
+
+/// A monotonic time source.
+pub trait Clock {
+    /// Time elapsed since an arbitrary, fixed epoch.
+    fn now(&self) -> Duration;
+}
+
+// --- vexide implementations ---
+
+#[cfg(target_vendor = "vex")]
+impl Encoder for RotationSensor {
+    fn angle(&self) -> Option<f64> {
+        RotationSensor::position(self).ok().map(|p| p.as_degrees())
+    }
+
+    fn reset(&mut self) {
+        let _ = self.reset_position();
+    }
+}
+
+/// Every vexide motor error comes down to the motor not answering.
+#[cfg(target_vendor = "vex")]
+fn motor<T, E>(result: Result<T, E>) -> Result<T, ActuatorError> {
+    result.map_err(|_| ActuatorError::Disconnected)
+}
+
+#[cfg(target_vendor = "vex")]
+impl Actuator for Motor {
+    fn set_voltage(&mut self, volts: f64) -> Result<(), ActuatorError> {
+        motor(Motor::set_voltage(self, volts))
+    }
+
+    fn set_velocity(&mut self, rpm: i32) -> Result<(), ActuatorError> {
+        motor(Motor::set_velocity(self, rpm))
+    }
+
+    fn brake(&mut self, mode: BrakeMode) -> Result<(), ActuatorError> {
+        motor(Motor::brake(self, mode))
+    }
+
+    fn voltage(&self) -> Result<f64, ActuatorError> {
+        motor(Motor::voltage(self))
+    }
+
+    fn position(&self) -> Result<f64, ActuatorError> {
+        motor(Motor::position(self)).map(|p| p.as_degrees())
+    }
+
+    fn reset_position(&mut self) -> Result<(), ActuatorError> {
+        motor(Motor::reset_position(self))
+    }
+
+    fn velocity(&self) -> Result<f64, ActuatorError> {
+        motor(Motor::velocity(self))
+    }
+
+    fn current(&self) -> Result<f64, ActuatorError> {
+        motor(Motor::current(self))
+    }
+
+    fn temperature(&self) -> Result<f64, ActuatorError> {
+        motor(Motor::temperature(self))
+    }
+
+    fn efficiency(&self) -> Result<f64, ActuatorError> {
+        motor(Motor::efficiency(self))
+    }
+
+    fn faults(&self) -> Result<(), ActuatorError> {
+        if motor(Motor::is_over_temperature(self))? {
+            return Err(ActuatorError::OverTemperature);
+        }
+        if motor(Motor::is_driver_fault(self))? || motor(Motor::is_driver_over_current(self))? {
+            return Err(ActuatorError::DriverFault);
+        }
+        if motor(Motor::is_over_current(self))? {
+            return Err(ActuatorError::OverCurrent);
+        }
+        Ok(())
+    }
+
+    fn gearset(&self) -> Result<Gearset, ActuatorError> {
+        motor(Motor::gearset(self))
+    }
+}
+
+#[cfg(target_vendor = "vex")]
+impl HeadingSource for InertialSensor {
+    fn heading(&self) -> Option<f64> {
+        InertialSensor::heading(self).ok()
+    }
+
+    fn calibrate(&mut self) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(async move {
+            let _ = InertialSensor::calibrate(self).await;
+        })
+    }
+}
+
+/// Wall clock of the Brain (or of the host, off the robot), measured from when
+/// the clock was created.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        Instant::now().duration_since(self.start)
+    }
+}
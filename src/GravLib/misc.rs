@@ -2,6 +2,10 @@ use vexide::devices::math::Point2;
 use vexide::prelude::*;
 use vexide::devices::{display::*};
 
+use alloc::format;
+
+use crate::GravLib::odom::pose::Pose;
+
 pub fn gravlib_logo(display: &mut Display) {
     // common colours & x‑position
     let yellow = Rgb::new(255, 255, 0);
@@ -61,3 +65,21 @@ pub fn gravlib_logo(display: &mut Display) {
         );
     }
 }
+
+/// Draws the current pose as a telemetry readout.
+pub fn draw_pose(display: &mut Display, pose: &Pose) {
+    let (x, y, theta) = pose.get_position();
+
+    display.set_render_mode(RenderMode::DoubleBuffered);
+    display.erase(Rgb::new(0, 0, 0));
+    display.draw_text(
+        &Text::new(
+            &format!("X: {:+.4}\nY: {:+.4}\nθ: {:.2}°", x, y, theta),
+            Font::new(FontSize::MEDIUM, FontFamily::Monospace),
+            Point2::<i16>::from([10, 10]),
+        ),
+        Rgb::new(255, 255, 255),
+        Some(Rgb::new(0, 0, 0)),
+    );
+    display.render();
+}
@@ -1,10 +1,17 @@
-
 pub mod actuator;
+pub mod hardware;
 pub mod pid;
 pub mod subsystems;
 pub mod odom;
+#[cfg(target_vendor = "vex")]
 pub mod misc;
 pub mod motions;
+pub mod path;
+#[cfg(not(target_vendor = "vex"))]
+pub mod sim;
+#[cfg(test)]
+mod testing;
 
 pub use pid::PID;
-pub use pid::Gains;
\ No newline at end of file
+pub use pid::Gains;
+pub use pid::exit_condition::ExitCondition;
\ No newline at end of file
@@ -0,0 +1,193 @@
+extern crate alloc;
+
+use core::{
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+
+use alloc::sync::Arc;
+use spin::Mutex;
+use vexide::{task, time::sleep};
+
+use crate::GravLib::actuator::MotorGroup;
+use crate::GravLib::hardware::BrakeMode;
+use crate::GravLib::motions::{handle::MotionProgress, motion_cancel_helper::MotionCancelHelper};
+use crate::GravLib::odom::{localisation::Localisation, pose::Pose};
+
+/// How often motion loops run.
+pub const MOTION_PERIOD: Duration = Duration::from_millis(10);
+
+/// How long a chained motion leaves the drive running for the next motion.
+/// If nothing has picked it up by then, the drive is stopped.
+pub const HANDOFF_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Everything a motion needs to drive the robot: both sides of the drivetrain
+/// and the pose estimate. Cheap to clone; all parts are shared.
+#[derive(Clone)]
+pub struct Chassis {
+    pub left: Arc<Mutex<MotorGroup>>,
+    pub right: Arc<Mutex<MotorGroup>>,
+    pub localisation: Arc<Mutex<Localisation>>,
+    /// Side powers a chained motion left the drive running at.
+    handoff: Arc<Mutex<Option<(f64, f64)>>>,
+    /// Counts handoffs, so a stale `HANDOFF_TIMEOUT` check can tell a newer
+    /// handoff from its own.
+    handoffs: Arc<AtomicU32>,
+    /// Progress tracker of the running `spawn_motion` task, which every
+    /// motion it starts reports into.
+    tracker: Arc<Mutex<Option<Arc<MotionProgress>>>>,
+}
+
+impl Chassis {
+    pub fn new(
+        left: Arc<Mutex<MotorGroup>>,
+        right: Arc<Mutex<MotorGroup>>,
+        localisation: Arc<Mutex<Localisation>>,
+    ) -> Self {
+        Self {
+            left,
+            right,
+            localisation,
+            handoff: Arc::new(Mutex::new(None)),
+            handoffs: Arc::new(AtomicU32::new(0)),
+            tracker: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn pose(&self) -> Pose {
+        self.localisation.lock().pose()
+    }
+
+    /// Drives each side at a fraction of full power, from -1.0 to 1.0.
+    pub fn drive(&self, left: f64, right: f64) {
+        self.drive_left(left);
+        self.drive_right(right);
+    }
+
+    // A motion can't do anything useful about a failed motor mid-loop, and
+    // the group still drives its healthy motors, so these don't report
+    // failures. The group logs each motor that starts failing, and
+    // `MotorGroup::check` reports which are unhealthy.
+
+    pub fn drive_left(&self, power: f64) {
+        let _ = self.left.lock().move_voltage(power * 12.0);
+    }
+
+    pub fn drive_right(&self, power: f64) {
+        let _ = self.right.lock().move_voltage(power * 12.0);
+    }
+
+    pub fn stop(&self) {
+        self.handoff.lock().take();
+        self.drive(0.0, 0.0);
+    }
+
+    /// If the previous motion exited early to chain into the next one, the
+    /// side powers it left the drive running at. The next motion takes this
+    /// to pick up from the robot's current speed instead of from rest.
+    pub fn take_handoff(&self) -> Option<(f64, f64)> {
+        self.handoff.lock().take()
+    }
+
+    /// Has every motion that starts from now on report into `progress`,
+    /// until `untrack`.
+    pub(crate) fn track(&self, progress: Arc<MotionProgress>) {
+        *self.tracker.lock() = Some(progress);
+    }
+
+    /// Stops motions reporting into `progress`, unless another tracker has
+    /// taken its place since.
+    pub(crate) fn untrack(&self, progress: &Arc<MotionProgress>) {
+        let mut tracker = self.tracker.lock();
+        if tracker.as_ref().is_some_and(|t| Arc::ptr_eq(t, progress)) {
+            *tracker = None;
+        }
+    }
+
+    /// Progress tracker for a motion that is starting now.
+    pub(crate) fn begin_progress(&self) -> Arc<MotionProgress> {
+        self.tracker
+            .lock()
+            .clone()
+            .unwrap_or_else(|| Arc::new(MotionProgress::new()))
+    }
+
+    /// Ends a motion. A motion that finished while `chain`ing leaves the drive
+    /// running at `last` for the next motion, for up to `HANDOFF_TIMEOUT`;
+    /// anything else stops the drive.
+    pub(crate) fn finish(&self, last: Option<(f64, f64)>, chain: bool) {
+        let Some(output) = last.filter(|_| chain) else {
+            self.stop();
+            return;
+        };
+
+        *self.handoff.lock() = Some(output);
+        let id = self.handoffs.fetch_add(1, Ordering::AcqRel) + 1;
+
+        // Don't leave the robot driving off on its own if this was the last
+        // motion, e.g. one with `min_speed` run outside a `MotionQueue`
+        let chassis = self.clone();
+        task::spawn(async move {
+            sleep(HANDOFF_TIMEOUT).await;
+            if chassis.handoffs.load(Ordering::Acquire) == id && chassis.handoff.lock().is_some() {
+                chassis.stop();
+            }
+        })
+        .detach();
+    }
+
+    pub fn brake(&self, mode: BrakeMode) {
+        let _ = self.left.lock().brake(mode);
+        let _ = self.right.lock().brake(mode);
+    }
+
+    /// Runs a motion's control law every 10ms until it returns `None`,
+    /// `timeout` passes, or the motion is cancelled.
+    ///
+    /// `step` gets the live pose and the time since its last call, and
+    /// returns the power for each side. If it finishes and `chain` is set,
+    /// the drive is left running for the next motion (see `HANDOFF_TIMEOUT`);
+    /// otherwise it stops.
+    pub async fn run(
+        &self,
+        timeout: Duration,
+        chain: bool,
+        step: impl FnMut(&Pose, Duration) -> Option<(f64, f64)>,
+    ) {
+        self.run_with(timeout, chain, step, |left, right| self.drive(left, right))
+            .await;
+    }
+
+    /// The loop behind every motion. Same as `run`, but `apply` sends the
+    /// side powers to the motors, for motions that don't drive both sides.
+    pub(crate) async fn run_with(
+        &self,
+        timeout: Duration,
+        chain: bool,
+        mut step: impl FnMut(&Pose, Duration) -> Option<(f64, f64)>,
+        mut apply: impl FnMut(f64, f64),
+    ) {
+        let mut helper = MotionCancelHelper::new(MOTION_PERIOD).with_timeout(timeout);
+        let progress = self.begin_progress();
+        let mut output = None;
+        let mut finished = None;
+
+        while helper.wait().await {
+            let pose = self.pose();
+            progress.record(&pose);
+
+            match step(&pose, helper.dt()) {
+                Some((left, right)) => {
+                    apply(left, right);
+                    output = Some((left, right));
+                }
+                None => {
+                    finished = output;
+                    break;
+                }
+            }
+        }
+
+        self.finish(finished, chain);
+    }
+}
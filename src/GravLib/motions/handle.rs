@@ -0,0 +1,135 @@
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::{future::Future, time::Duration};
+
+use spin::Mutex;
+use uom::si::{angle::degree, length::inch};
+use vexide::{task, time::sleep};
+
+use crate::GravLib::motions::chassis::Chassis;
+use crate::GravLib::odom::pose::wrap_degrees;
+use crate::GravLib::odom::pose::Pose;
+
+/// How often the `wait_until` helpers check progress.
+const POLL_PERIOD: Duration = Duration::from_millis(10);
+
+struct Progress {
+    last_pose: Option<Pose>,
+    /// Path length driven, inches.
+    distance: f64,
+    /// Total rotation, degrees, regardless of direction.
+    angle: f64,
+    done: bool,
+}
+
+/// How far a motion has got. Updated by the motion itself every iteration.
+/// For a `spawn_motion` task running several motions, it covers all of them
+/// and is only done once the whole task has finished.
+pub struct MotionProgress {
+    inner: Mutex<Progress>,
+}
+
+impl MotionProgress {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(Progress {
+                last_pose: None,
+                distance: 0.0,
+                angle: 0.0,
+                done: false,
+            }),
+        }
+    }
+
+    pub(crate) fn record(&self, pose: &Pose) {
+        let mut p = self.inner.lock();
+        if let Some(last) = p.last_pose {
+            p.distance += last.distance_to(pose).get::<inch>();
+            p.angle += wrap_degrees((pose.theta() - last.theta()).get::<degree>()).abs();
+        }
+        p.last_pose = Some(*pose);
+    }
+
+    pub(crate) fn finish(&self) {
+        self.inner.lock().done = true;
+    }
+
+    /// Distance driven so far, in inches.
+    pub fn distance(&self) -> f64 {
+        self.inner.lock().distance
+    }
+
+    /// Rotation so far, in degrees, counting both directions.
+    pub fn angle(&self) -> f64 {
+        self.inner.lock().angle
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.inner.lock().done
+    }
+}
+
+/// A motion running in the background, returned by `spawn_motion`.
+pub struct MotionHandle {
+    progress: Arc<MotionProgress>,
+}
+
+impl MotionHandle {
+    pub fn progress(&self) -> &MotionProgress {
+        &self.progress
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.progress.is_done()
+    }
+
+    async fn wait_for(&self, reached: impl Fn(&MotionProgress) -> bool) {
+        while !reached(&self.progress) && !self.progress.is_done() {
+            sleep(POLL_PERIOD).await;
+        }
+    }
+
+    /// Waits until the robot has driven `distance` inches, or the motion ends.
+    pub async fn wait_until_distance(&self, distance: f64) {
+        self.wait_for(|p| p.distance() >= distance).await;
+    }
+
+    /// Waits until the robot has turned `angle` degrees, or the motion ends.
+    pub async fn wait_until_angle(&self, angle: f64) {
+        self.wait_for(|p| p.angle() >= angle).await;
+    }
+
+    pub async fn wait_until_done(&self) {
+        self.wait_for(|_| false).await;
+    }
+}
+
+/// Starts `motion` on its own task and returns straight away, so the caller
+/// can work other mechanisms while the robot moves:
+///
+/// ```ignore
+/// let c = chassis.clone();
+/// let handle = spawn_motion(&chassis, async move {
+///     move_to_point(&c, 24.0, 48.0, timeout, params, &mut settings).await
+/// });
+/// handle.wait_until_distance(12.0).await;
+/// intake.move_voltage(12.0);
+/// handle.wait_until_done().await;
+/// ```
+pub fn spawn_motion(chassis: &Chassis, motion: impl Future<Output = ()> + 'static) -> MotionHandle {
+    let progress = Arc::new(MotionProgress::new());
+    let tracked = Arc::clone(&progress);
+    let chassis = chassis.clone();
+
+    task::spawn(async move {
+        // Every motion the task runs picks this up when it starts
+        chassis.track(Arc::clone(&tracked));
+        motion.await;
+        chassis.untrack(&tracked);
+        tracked.finish();
+    })
+    .detach();
+
+    MotionHandle { progress }
+}
@@ -1 +1,23 @@
-mod motion_cancel_helper;
\ No newline at end of file
+// The control laws and helpers build anywhere; running them on the robot
+// (`Chassis` and everything that paces or spawns a motion) needs
+// vexide.
+#[cfg(target_vendor = "vex")]
+pub mod chassis;
+#[cfg(target_vendor = "vex")]
+pub mod handle;
+pub mod motion_cancel_helper;
+pub mod move_to_point;
+pub mod move_to_pose;
+pub mod pure_pursuit;
+#[cfg(target_vendor = "vex")]
+pub mod queue;
+pub mod turn_to;
+pub mod util;
+
+#[cfg(target_vendor = "vex")]
+pub use chassis::Chassis;
+#[cfg(target_vendor = "vex")]
+pub use handle::{spawn_motion, MotionHandle};
+pub use motion_cancel_helper::{cancel_all_motions, cancel_motion, is_motion_running};
+#[cfg(target_vendor = "vex")]
+pub use queue::{Motion, MotionQueue};
@@ -1,57 +1,185 @@
-use vexide::time::{sleep_until, Instant};
-use vexide::competition;
+use core::sync::atomic::{AtomicU32, Ordering};
+#[cfg(target_vendor = "vex")]
 use core::time::Duration;
-use vexide::task;
+#[cfg(target_vendor = "vex")]
+use vexide::competition;
+#[cfg(target_vendor = "vex")]
+use vexide::time::{sleep_until, Instant};
+
+/// Number of motion loops running. A spawned motion can run alongside another,
+/// so one finishing doesn't mean none are left.
+static MOTIONS_RUNNING: AtomicU32 = AtomicU32::new(0);
+/// Bumped by `cancel_motion`. Motions running at the time see a different
+/// value and stop; ones started afterwards run as normal.
+static CANCEL_CURRENT: AtomicU32 = AtomicU32::new(0);
+/// Bumped by `cancel_all_motions`. Motions and queues started before the bump
+/// see a different value and stop.
+static CANCEL_EPOCH: AtomicU32 = AtomicU32::new(0);
+
+/// Stops the motion that is currently running. The next motion, e.g. the
+/// next one in a `MotionQueue`, starts as normal.
+pub fn cancel_motion() {
+    if is_motion_running() {
+        CANCEL_CURRENT.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Stops the running motion and everything queued behind it.
+pub fn cancel_all_motions() {
+    CANCEL_EPOCH.fetch_add(1, Ordering::AcqRel);
+    cancel_motion();
+}
+
+pub fn is_motion_running() -> bool {
+    MOTIONS_RUNNING.load(Ordering::Acquire) > 0
+}
+
+/// Current value of the `cancel_all_motions` counter. Anything that runs
+/// several motions in a row compares against this to know when to give up.
+pub(crate) fn cancel_epoch() -> u32 {
+    CANCEL_EPOCH.load(Ordering::Acquire)
+}
+
+/// One running motion's claim on the cancel state, for loops that pace
+/// themselves. Creating it counts a motion as running and dropping it takes
+/// that count away again.
+pub struct MotionToken {
+    m_cancel: u32,
+    m_epoch: u32,
+}
+
+impl MotionToken {
+    pub fn start() -> Self {
+        MOTIONS_RUNNING.fetch_add(1, Ordering::AcqRel);
+        MotionToken {
+            m_cancel: CANCEL_CURRENT.load(Ordering::Acquire),
+            m_epoch: cancel_epoch(),
+        }
+    }
+
+    /// Whether `cancel_motion` or `cancel_all_motions` was called since this
+    /// motion started.
+    pub fn is_cancelled(&self) -> bool {
+        CANCEL_CURRENT.load(Ordering::Acquire) != self.m_cancel || cancel_epoch() != self.m_epoch
+    }
+}
 
+impl Drop for MotionToken {
+    fn drop(&mut self) {
+        MOTIONS_RUNNING.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Drives a motion's loop: paces it at a fixed period and decides when it has
+/// to stop. One exists per running motion, and it counts as running for as
+/// long as the helper lives.
+#[cfg(target_vendor = "vex")]
 pub struct MotionCancelHelper {
     m_firstIteration: bool,
     m_prevTime: Instant,
+    m_lastIteration: Instant,
+    m_startTime: Instant,
+    m_dt: Duration,
     m_originalCompStatus: competition::CompetitionMode,
-    m_period: Duration
+    m_token: MotionToken,
+    m_period: Duration,
+    m_timeout: Option<Duration>,
 }
 
-/**
- *  const int64_t now = int64_t(pros::millis());
-    if (now - int64_t(m_prevTime) > int64_t(processedTimeout)) m_prevTime = now - processedTimeout;
-    // only delay if this is not the first iteration
-    if (!m_firstIteration) pros::Task::delay_until(&m_prevTime, processedTimeout);
-    else m_firstIteration = false;
- */
-
+#[cfg(target_vendor = "vex")]
 impl MotionCancelHelper {
     pub fn new(period: Duration) -> Self {
-        let status = competition::status().mode();
+        let now = Instant::now();
         MotionCancelHelper {
             m_firstIteration: true,
-            m_prevTime: Instant::now() - period, 
-            m_originalCompStatus: status,
-            m_period: period
+            m_prevTime: now,
+            m_lastIteration: now,
+            m_startTime: now,
+            m_dt: Duration::ZERO,
+            m_originalCompStatus: competition::status().mode(),
+            m_token: MotionToken::start(),
+            m_period: period,
+            m_timeout: None,
         }
     }
 
-    pub fn wait(&mut self) -> bool {
-        let mut m_prevPrevTime: Instant = self.m_prevTime;
-        let processedTimeout: Duration = self.m_period; 
-        
-        let status = competition::status().mode();
-
-        let now =  Instant::now(); 
-        if now - self.m_prevTime > processedTimeout {
-            self.m_prevTime = now - processedTimeout;
-        }
+    /// Stops the motion once `timeout` has passed since it started.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.m_timeout = Some(timeout);
+        self
+    }
 
-        // delay if not first iteration
-        if !self.m_firstIteration { 
-            sleep_until(self.m_prevTime + processedTimeout);
+    /// Sleeps until the next period, then reports whether the motion should
+    /// keep running. Returns `false` once the competition mode has changed,
+    /// the motion was cancelled or it has timed out.
+    pub async fn wait(&mut self) -> bool {
+        // Don't sleep before the first iteration
+        if !self.m_firstIteration {
+            self.m_prevTime += self.m_period;
+            // If an iteration overran, start counting again from now rather
+            // than running several iterations back to back to catch up
+            let now = Instant::now();
+            if now > self.m_prevTime {
+                self.m_prevTime = now;
+            }
+            sleep_until(self.m_prevTime).await;
         } else {
             self.m_firstIteration = false;
         }
 
-        // different competition status as started, exit motion
-        if status != self.m_originalCompStatus {
-            false // end motion
+        let now = Instant::now();
+        self.m_dt = now - self.m_lastIteration;
+        self.m_lastIteration = now;
+
+        if competition::status().mode() != self.m_originalCompStatus {
+            return false;
+        }
+        if self.m_token.is_cancelled() {
+            return false;
         }
+        if let Some(timeout) = self.m_timeout {
+            if now - self.m_startTime >= timeout {
+                return false;
+            }
+        }
+        true
+    }
 
-        
+    /// Time between the last two iterations. Zero on the first one.
+    pub fn dt(&self) -> Duration {
+        self.m_dt
     }
-}
\ No newline at end of file
+
+    /// Time since the motion started.
+    pub fn elapsed(&self) -> Duration {
+        self.m_startTime.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The cancel state is global, so this is one test rather than several
+    // that could run at the same time
+    #[test]
+    fn cancels_reach_only_the_motions_running_at_the_time() {
+        let first = MotionToken::start();
+        assert!(is_motion_running());
+
+        // A second motion starting doesn't clear the first one's cancel
+        cancel_motion();
+        let second = MotionToken::start();
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+
+        // Cancelling everything reaches every motion still running
+        let third = MotionToken::start();
+        cancel_all_motions();
+        assert!(second.is_cancelled() && third.is_cancelled());
+        assert!(!MotionToken::start().is_cancelled());
+
+        drop((first, second, third));
+        assert!(!is_motion_running());
+    }
+}
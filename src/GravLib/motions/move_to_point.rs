@@ -0,0 +1,164 @@
+use core::time::Duration;
+
+use uom::si::{angle::degree, length::inch};
+
+use crate::GravLib::{ExitCondition, PID};
+use crate::GravLib::odom::pose::Pose;
+use crate::GravLib::motions::util::{constrain_speed, desaturate, slew, CLOSE_DISTANCE};
+use crate::GravLib::odom::pose::wrap_degrees;
+#[cfg(target_vendor = "vex")]
+use crate::GravLib::motions::chassis::Chassis;
+
+
+#[derive(Clone, Copy, Debug)]
+pub struct MoveToPointParams {
+    /// Drive forwards to the point, or backwards if `false`.
+    pub forwards: bool,
+    /// Fraction of full power, 0.0 to 1.0.
+    pub max_speed: f64,
+    /// Fraction of full power, 0.0 to 1.0. Non-zero values make the motion
+    /// exit as soon as it passes the target, for chaining.
+    pub min_speed: f64,
+    /// Largest increase in lateral power per 10ms iteration. 0 disables slew.
+    pub slew: f64,
+    /// Exit once within this many inches of the target. 0 disables it.
+    pub early_exit_range: f64,
+}
+
+impl Default for MoveToPointParams {
+    fn default() -> Self {
+        Self {
+            forwards: true,
+            max_speed: 1.0,
+            min_speed: 0.0,
+            slew: 0.0,
+            early_exit_range: 0.0,
+        }
+    }
+}
+
+pub struct MoveToSettings {
+    /// Takes distance error in inches, outputs a fraction of full power.
+    pub lateral_pid: PID,
+    /// Takes heading error in degrees, outputs a fraction of full power.
+    pub angular_pid: PID,
+    /// When the motion is finished, checked against the distance to the
+    /// target in inches.
+    pub exit: ExitCondition,
+}
+
+/// The control law for `move_to_point`, with no hardware or timing of its own.
+pub struct MoveToPointController<'a> {
+    target: Pose,
+    params: MoveToPointParams,
+    settings: &'a mut MoveToSettings,
+
+    /// Direction from the start to the target, to tell when it's been passed.
+    approach: Option<(f64, f64)>,
+    max_speed: f64,
+    close: bool,
+    prev_lateral: f64,
+}
+
+impl<'a> MoveToPointController<'a> {
+    /// `x` and `y` are a field point in inches.
+    pub fn new(x: f64, y: f64, params: MoveToPointParams, settings: &'a mut MoveToSettings) -> Self {
+        settings.lateral_pid.reset();
+        settings.angular_pid.reset();
+        settings.exit.reset();
+        Self {
+            target: Pose::from_inches(x, y, 0.0),
+            params,
+            settings,
+            approach: None,
+            max_speed: params.max_speed,
+            close: false,
+            prev_lateral: 0.0,
+        }
+    }
+
+    /// Starts slewing from the forward power of the previous motion's output,
+    /// rather than from rest.
+    pub fn carry(&mut self, left: f64, right: f64) {
+        self.prev_lateral = (left + right) / 2.0;
+    }
+
+    /// Returns the power for each side, or `None` once the motion is done.
+    pub fn step(&mut self, pose: &Pose, dt: Duration) -> Option<(f64, f64)> {
+        let (x, y, theta) = pose.get_position();
+        let (tx, ty, _) = self.target.get_position();
+        let distance = pose.distance_to(&self.target).get::<inch>();
+
+        // Which side of the line through the target, perpendicular to the
+        // approach, the robot is on. Positive means it has gone past.
+        let (ax, ay) = *self.approach.get_or_insert_with(|| {
+            let len = libm::hypot(tx - x, ty - y).max(f64::EPSILON);
+            ((tx - x) / len, (ty - y) / len)
+        });
+        let past = (x - tx) * ax + (y - ty) * ay > 0.0;
+
+        if self.params.early_exit_range > 0.0 && distance < self.params.early_exit_range {
+            return None;
+        }
+        if self.params.min_speed != 0.0 && past {
+            return None;
+        }
+
+        if self.settings.exit.update(distance, dt) {
+            return None;
+        }
+
+        if !self.close && distance < CLOSE_DISTANCE {
+            self.close = true;
+            self.max_speed = self.prev_lateral.abs().max(0.5).min(self.params.max_speed);
+        }
+
+        // Heading error for whichever end of the robot leads
+        let facing = if self.params.forwards { theta } else { theta + 180.0 };
+        let bearing = pose.angle_to(&self.target).get::<degree>();
+        let angular_error = if self.close { 0.0 } else { wrap_degrees(bearing - facing) };
+
+        // Only drive as fast as the robot is pointed at the target
+        let lateral_error = distance * libm::cos(wrap_degrees(bearing - theta).to_radians());
+
+        let lateral = self.settings.lateral_pid.update_with_dt(lateral_error as f32, dt) as f64;
+        let angular = self.settings.angular_pid.update_with_dt(angular_error as f32, dt) as f64;
+
+        let mut lateral = lateral.clamp(-self.max_speed, self.max_speed);
+        if !self.close {
+            lateral = slew(lateral, self.prev_lateral, self.params.slew);
+            // Never reverse out of the requested direction while steering in
+            lateral = if self.params.forwards { lateral.max(0.0) } else { lateral.min(0.0) };
+        }
+        let lateral = constrain_speed(lateral, self.max_speed, self.params.min_speed);
+        let angular = angular.clamp(-self.max_speed, self.max_speed);
+        self.prev_lateral = lateral;
+
+        Some(desaturate(lateral, angular, self.max_speed))
+    }
+}
+
+/// Drives to the field point `(x, y)`, in inches, steering with the angular
+/// PID while the lateral PID closes the distance.
+///
+/// Ends when the robot settles, the early exit range is reached, `timeout`
+/// passes, or the motion is cancelled. With `min_speed` or `early_exit_range`
+/// set, the drive is left running for the next motion to pick up, and stops
+/// if none starts within `HANDOFF_TIMEOUT`.
+#[cfg(target_vendor = "vex")]
+pub async fn move_to_point(
+    chassis: &Chassis,
+    x: f64,
+    y: f64,
+    timeout: Duration,
+    params: MoveToPointParams,
+    settings: &mut MoveToSettings,
+) {
+    let mut controller = MoveToPointController::new(x, y, params, settings);
+    if let Some((left, right)) = chassis.take_handoff() {
+        controller.carry(left, right);
+    }
+
+    let chain = params.min_speed > 0.0 || params.early_exit_range > 0.0;
+    chassis.run(timeout, chain, |pose, dt| controller.step(pose, dt)).await;
+}
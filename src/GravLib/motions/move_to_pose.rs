@@ -0,0 +1,266 @@
+use core::time::Duration;
+
+use uom::si::{angle::degree, length::inch};
+
+use crate::GravLib::odom::pose::{wrap_degrees, Pose};
+use crate::GravLib::motions::{
+    move_to_point::MoveToSettings,
+    util::{arc_curvature, constrain_speed, desaturate, slew, CLOSE_DISTANCE},
+};
+#[cfg(target_vendor = "vex")]
+use crate::GravLib::motions::chassis::Chassis;
+
+
+#[derive(Clone, Copy, Debug)]
+pub struct MoveToPoseParams {
+    /// Drive forwards into the pose, or backwards if `false`.
+    pub forwards: bool,
+    /// How far behind the target the carrot point starts, as a fraction of
+    /// the distance to it. Higher values swing wider. 0 drives straight at
+    /// the target.
+    pub lead: f64,
+    /// Sideways acceleration (in/s²) the wheels can hold before they slide
+    /// out. On a curve the robot slows to the speed that keeps within it.
+    /// Needs `top_speed`; 0 disables the limit.
+    pub horizontal_drift: f64,
+    /// Speed of the drive at full power in inches per second, e.g. from
+    /// `DriveTrain::max_speed`. Turns `horizontal_drift` into a power limit.
+    pub top_speed: f64,
+    /// Fraction of full power, 0.0 to 1.0.
+    pub max_speed: f64,
+    /// Fraction of full power, 0.0 to 1.0. Non-zero values make the motion
+    /// exit as soon as it passes the target, for chaining.
+    pub min_speed: f64,
+    /// Largest increase in lateral power per 10ms iteration. 0 disables slew.
+    pub slew: f64,
+    /// Exit once within this many inches of the target. 0 disables it.
+    pub early_exit_range: f64,
+    /// Heading error (degrees) that still counts as settled at the target.
+    pub heading_tolerance: f64,
+}
+
+impl Default for MoveToPoseParams {
+    fn default() -> Self {
+        Self {
+            forwards: true,
+            lead: 0.6,
+            horizontal_drift: 0.0,
+            top_speed: 0.0,
+            max_speed: 1.0,
+            min_speed: 0.0,
+            slew: 0.0,
+            early_exit_range: 0.0,
+            heading_tolerance: 2.0,
+        }
+    }
+}
+
+/// The boomerang control law for `move_to_pose`, with no hardware or timing
+/// of its own.
+///
+/// Instead of aiming at the target, the robot chases a "carrot" point placed
+/// behind the target along its final heading, `lead` times the remaining
+/// distance away. As the robot closes in the carrot slides onto the target,
+/// so the robot arrives already facing the right way.
+pub struct MoveToPoseController<'a> {
+    target: Pose,
+    params: MoveToPoseParams,
+    settings: &'a mut MoveToSettings,
+
+    max_speed: f64,
+    close: bool,
+    prev_lateral: f64,
+}
+
+impl<'a> MoveToPoseController<'a> {
+    /// `x` and `y` are in inches and `theta` is a field heading in degrees.
+    pub fn new(
+        x: f64,
+        y: f64,
+        theta: f64,
+        params: MoveToPoseParams,
+        settings: &'a mut MoveToSettings,
+    ) -> Self {
+        settings.lateral_pid.reset();
+        settings.angular_pid.reset();
+        settings.exit.reset();
+        Self {
+            target: Pose::from_inches(x, y, theta),
+            params,
+            settings,
+            max_speed: params.max_speed,
+            close: false,
+            prev_lateral: 0.0,
+        }
+    }
+
+    /// Direction the robot travels as it arrives, as a unit field vector.
+    fn arrival_direction(&self) -> (f64, f64) {
+        let theta = self.target.theta().get::<degree>().to_radians();
+        let sign = if self.params.forwards { 1.0 } else { -1.0 };
+        (sign * libm::sin(theta), sign * libm::cos(theta))
+    }
+
+    /// Starts slewing from the forward power of the previous motion's output,
+    /// rather than from rest.
+    pub fn carry(&mut self, left: f64, right: f64) {
+        self.prev_lateral = (left + right) / 2.0;
+    }
+
+    /// Returns the power for each side, or `None` once the motion is done.
+    pub fn step(&mut self, pose: &Pose, dt: Duration) -> Option<(f64, f64)> {
+        let (x, y, theta) = pose.get_position();
+        let (tx, ty, target_theta) = self.target.get_position();
+        let distance = pose.distance_to(&self.target).get::<inch>();
+        let (dx, dy) = self.arrival_direction();
+
+        // Whichever end of the robot leads
+        let facing = if self.params.forwards { theta } else { theta + 180.0 };
+        let final_error = wrap_degrees(target_theta - theta);
+
+        if self.params.early_exit_range > 0.0 && distance < self.params.early_exit_range {
+            return None;
+        }
+        // Past the line through the target, square to the arrival direction
+        if self.params.min_speed != 0.0 && (x - tx) * dx + (y - ty) * dy > 0.0 {
+            return None;
+        }
+
+        // Settled on distance, but only finished once facing the right way too
+        let settled = self.settings.exit.update(distance, dt);
+        if settled && final_error.abs() < self.params.heading_tolerance {
+            return None;
+        }
+
+        // Close in, stop chasing the carrot and turn to the final heading
+        if !self.close && distance < CLOSE_DISTANCE {
+            self.close = true;
+            self.max_speed = self.prev_lateral.abs().max(0.5).min(self.params.max_speed);
+        }
+
+        // Carrot point, pulled back from the target along the arrival direction
+        let (cx, cy) = if self.close {
+            (tx, ty)
+        } else {
+            let pull = self.params.lead * distance;
+            (tx - dx * pull, ty - dy * pull)
+        };
+        let carrot = Pose::from_inches(cx, cy, 0.0);
+        let bearing = pose.angle_to(&carrot).get::<degree>();
+
+        // Near the end, square up to the final heading rather than the carrot
+        let angular_error = if self.close {
+            wrap_degrees(target_theta - theta)
+        } else {
+            wrap_degrees(bearing - facing)
+        };
+
+        let scale = libm::cos(wrap_degrees(bearing - theta).to_radians());
+        let lateral_error = pose.distance_to(&carrot).get::<inch>()
+            * if self.close { scale } else { scale.signum() };
+
+        let lateral = self.settings.lateral_pid.update_with_dt(lateral_error as f32, dt) as f64;
+        let angular = self.settings.angular_pid.update_with_dt(angular_error as f32, dt) as f64;
+
+        let mut lateral = lateral.clamp(-self.max_speed, self.max_speed);
+        let angular = angular.clamp(-self.max_speed, self.max_speed);
+
+        if !self.close {
+            lateral = slew(lateral, self.prev_lateral, self.params.slew);
+
+            // Slow down on tight curves so the wheels don't slide out: on an
+            // arc of curvature k, speed v needs v²k of sideways grip
+            if self.params.horizontal_drift > 0.0 && self.params.top_speed > 0.0 {
+                let curvature = arc_curvature(pose, cx, cy).abs();
+                if curvature > 0.0 {
+                    let max_speed = libm::sqrt(self.params.horizontal_drift / curvature);
+                    let max_power = max_speed / self.params.top_speed;
+                    lateral = lateral.clamp(-max_power, max_power);
+                }
+            }
+
+            lateral = if self.params.forwards { lateral.max(0.0) } else { lateral.min(0.0) };
+        }
+
+        let lateral = constrain_speed(lateral, self.max_speed, self.params.min_speed);
+        self.prev_lateral = lateral;
+
+        Some(desaturate(lateral, angular, self.max_speed))
+    }
+}
+
+/// Drives to the field pose `(x, y, theta)` (inches, degrees) along a smooth
+/// curve, arriving at the final heading.
+///
+/// Ends when the robot settles, the early exit range is reached, `timeout`
+/// passes, or the motion is cancelled. With `min_speed` or `early_exit_range`
+/// set, the drive is left running for the next motion to pick up, and stops
+/// if none starts within `HANDOFF_TIMEOUT`.
+#[cfg(target_vendor = "vex")]
+pub async fn move_to_pose(
+    chassis: &Chassis,
+    x: f64,
+    y: f64,
+    theta: f64,
+    timeout: Duration,
+    params: MoveToPoseParams,
+    settings: &mut MoveToSettings,
+) {
+    let mut controller = MoveToPoseController::new(x, y, theta, params, settings);
+    if let Some((left, right)) = chassis.take_handoff() {
+        controller.carry(left, right);
+    }
+
+    let chain = params.min_speed > 0.0 || params.early_exit_range > 0.0;
+    chassis.run(timeout, chain, |pose, dt| controller.step(pose, dt)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GravLib::{ExitCondition, PID};
+
+    fn settings() -> MoveToSettings {
+        MoveToSettings {
+            lateral_pid: PID::new(1.0, 0.0, 0.0, 0.0, false),
+            angular_pid: PID::new(0.0, 0.0, 0.0, 0.0, false),
+            exit: ExitCondition::range(1.0, Duration::from_millis(100)),
+        }
+    }
+
+    /// Power for the first step towards (10, 10), an arc of curvature 0.1/in
+    /// from the origin.
+    fn first_step(horizontal_drift: f64) -> (f64, f64) {
+        let params = MoveToPoseParams { lead: 0.0, horizontal_drift, top_speed: 80.0, ..Default::default() };
+        let mut settings = settings();
+        let mut controller = MoveToPoseController::new(10.0, 10.0, 90.0, params, &mut settings);
+        controller.step(&Pose::default(), Duration::from_millis(10)).unwrap()
+    }
+
+    #[test]
+    fn horizontal_drift_limits_speed_on_curves() {
+        // sqrt(40 / 0.1) = 20 in/s, a quarter of the top speed
+        let (left, right) = first_step(40.0);
+        assert!((left - 0.25).abs() < 1e-9 && (right - 0.25).abs() < 1e-9, "({left}, {right})");
+    }
+
+    #[test]
+    fn horizontal_drift_of_zero_is_unlimited() {
+        assert_eq!(first_step(0.0), (1.0, 1.0));
+    }
+
+    #[test]
+    fn driving_and_steering_share_full_power_like_move_to_point() {
+        let mut settings = MoveToSettings {
+            angular_pid: PID::new(0.02, 0.0, 0.0, 0.0, false),
+            ..settings()
+        };
+        let params = MoveToPoseParams { lead: 0.0, ..Default::default() };
+        let mut controller = MoveToPoseController::new(30.0, 30.0, 45.0, params, &mut settings);
+
+        // Both saturate: 1.0 lateral and 0.9 angular, scaled down together
+        let (left, right) = controller.step(&Pose::default(), Duration::from_millis(10)).unwrap();
+        assert!((left - 1.0).abs() < 1e-6, "({left}, {right})");
+        assert!((right - 0.1 / 1.9).abs() < 1e-6, "({left}, {right})");
+    }
+}
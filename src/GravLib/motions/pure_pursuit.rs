@@ -0,0 +1,296 @@
+use core::time::Duration;
+
+use crate::GravLib::odom::pose::Pose;
+use crate::GravLib::path::{Path, Waypoint};
+use crate::GravLib::motions::util::arc_curvature;
+#[cfg(target_vendor = "vex")]
+use crate::GravLib::motions::chassis::Chassis;
+
+#[derive(Clone, Copy, Debug)]
+pub struct FollowPathParams {
+    /// Radius (inches) of the circle the lookahead point is found on. Larger
+    /// values follow more smoothly but cut corners.
+    pub lookahead: f64,
+    /// The path is done once the robot is this many inches from its end.
+    pub end_tolerance: f64,
+    /// Follow the path driving backwards.
+    pub reversed: bool,
+    /// Fraction of full power, 0.0 to 1.0. Waypoint speeds are scaled by it.
+    pub max_speed: f64,
+    /// Fraction of full power, 0.0 to 1.0. The robot never follows slower
+    /// than this, and a non-zero value leaves it moving at the end of the
+    /// path for the next motion.
+    pub min_speed: f64,
+    /// Distance between the left and right wheels, in inches.
+    pub track_width: f64,
+}
+
+impl Default for FollowPathParams {
+    fn default() -> Self {
+        Self {
+            lookahead: 15.0,
+            end_tolerance: 2.0,
+            reversed: false,
+            max_speed: 1.0,
+            min_speed: 0.0,
+            track_width: 12.0,
+        }
+    }
+}
+
+/// Where a circle of radius `radius` about `(cx, cy)` leaves the segment
+/// `a`→`b`, as a fraction along the segment. Takes the exit furthest along.
+fn circle_intersection(a: &Waypoint, b: &Waypoint, cx: f64, cy: f64, radius: f64) -> Option<f64> {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let (fx, fy) = (a.x - cx, a.y - cy);
+
+    let qa = dx * dx + dy * dy;
+    let qb = 2.0 * (fx * dx + fy * dy);
+    let qc = fx * fx + fy * fy - radius * radius;
+    let discriminant = qb * qb - 4.0 * qa * qc;
+    if qa == 0.0 || discriminant < 0.0 {
+        return None;
+    }
+
+    let root = libm::sqrt(discriminant);
+    let far = (-qb + root) / (2.0 * qa);
+    let near = (-qb - root) / (2.0 * qa);
+    [far, near].into_iter().find(|t| (0.0..=1.0).contains(t))
+}
+
+/// The pure pursuit control law, with no hardware or timing of its own.
+pub struct PurePursuitController<'a> {
+    path: &'a Path,
+    params: FollowPathParams,
+
+    closest: usize,
+    /// Lookahead point as a segment index plus fraction along it.
+    lookahead: (usize, f64),
+}
+
+impl<'a> PurePursuitController<'a> {
+    pub fn new(path: &'a Path, params: FollowPathParams) -> Self {
+        Self {
+            path,
+            params,
+            closest: 0,
+            lookahead: (0, 0.0),
+        }
+    }
+
+    /// Index of the path point nearest the robot. Only searches forwards, and
+    /// no further along the path than the lookahead distance, so the robot
+    /// can't skip back to an earlier part of a crossing path or jump ahead
+    /// to a later one.
+    fn update_closest(&mut self, x: f64, y: f64) {
+        let here = Waypoint::new(x, y, 0.0);
+        let points = &self.path.points;
+        let mut best = self.closest;
+        let mut best_distance = f64::MAX;
+        let mut travelled = 0.0;
+        for i in self.closest..points.len() {
+            if i > self.closest {
+                travelled += points[i - 1].distance_to(&points[i]);
+                if travelled > self.params.lookahead {
+                    break;
+                }
+            }
+            let d = here.distance_to(&points[i]);
+            if d < best_distance {
+                best = i;
+                best_distance = d;
+            }
+        }
+        self.closest = best;
+    }
+
+    /// Furthest point along the path that is exactly `lookahead` away. Keeps
+    /// the previous one if the circle doesn't cross the path.
+    fn update_lookahead(&mut self, x: f64, y: f64) -> (f64, f64) {
+        let points = &self.path.points;
+        let start = self.lookahead.0.max(self.closest.saturating_sub(1));
+        for i in start..points.len().saturating_sub(1) {
+            if let Some(t) = circle_intersection(&points[i], &points[i + 1], x, y, self.params.lookahead) {
+                if (i, t) > self.lookahead {
+                    self.lookahead = (i, t);
+                }
+            }
+        }
+
+        // Near the end the circle overruns the path; aim at the last point
+        let last = points[points.len() - 1];
+        if last.distance_to(&Waypoint::new(x, y, 0.0)) < self.params.lookahead {
+            return (last.x, last.y);
+        }
+
+        let (i, t) = self.lookahead;
+        let a = points[i];
+        let b = points[(i + 1).min(points.len() - 1)];
+        (a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+    }
+
+    /// Returns the power for each side, or `None` once the path is done.
+    pub fn step(&mut self, pose: &Pose, _dt: Duration) -> Option<(f64, f64)> {
+        let end = self.path.last()?;
+        let (x, y, theta) = pose.get_position();
+
+        self.update_closest(x, y);
+        if end.distance_to(&Waypoint::new(x, y, 0.0)) < self.params.end_tolerance
+            || self.closest == self.path.len() - 1
+        {
+            return None;
+        }
+
+        let (lx, ly) = self.update_lookahead(x, y);
+
+        // Driving backwards is driving forwards with the robot turned around
+        // and its sides swapped
+        let virtual_pose = if self.params.reversed {
+            Pose::from_inches(x, y, theta + 180.0)
+        } else {
+            *pose
+        };
+        let curvature = arc_curvature(&virtual_pose, lx, ly);
+
+        let speed = (self.path.points[self.closest].speed * self.params.max_speed)
+            .max(self.params.min_speed);
+        let half_turn = curvature * self.params.track_width / 2.0;
+        let mut left = speed * (1.0 + half_turn);
+        let mut right = speed * (1.0 - half_turn);
+
+        // Keep the inside/outside ratio if the outside wheel would saturate
+        let peak = left.abs().max(right.abs());
+        if peak > self.params.max_speed {
+            left *= self.params.max_speed / peak;
+            right *= self.params.max_speed / peak;
+        }
+
+        if self.params.reversed {
+            Some((-right, -left))
+        } else {
+            Some((left, right))
+        }
+    }
+}
+
+/// Follows `path` with pure pursuit: each iteration the robot steers along
+/// the arc to a point `lookahead` inches further down the path, at the speed
+/// recorded at the nearest waypoint.
+///
+/// Ends at the end of the path, when `timeout` passes, or when the motion is
+/// cancelled.
+#[cfg(target_vendor = "vex")]
+pub async fn follow_path(chassis: &Chassis, path: &Path, timeout: Duration, params: FollowPathParams) {
+    if path.is_empty() {
+        return;
+    }
+
+    // Pure pursuit has no slew to seed, but the handoff is still consumed
+    chassis.take_handoff();
+
+    let mut controller = PurePursuitController::new(path, params);
+    chassis.run(timeout, params.min_speed > 0.0, |pose, dt| controller.step(pose, dt)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Straight path along +y from the origin, one point per inch.
+    fn straight(length: usize) -> Path {
+        Path::new((0..=length).map(|y| Waypoint::new(0.0, y as f64, 1.0)).collect())
+    }
+
+    #[test]
+    fn circle_meets_segment_where_it_leaves() {
+        let (a, b) = (Waypoint::new(0.0, 0.0, 0.0), Waypoint::new(0.0, 10.0, 0.0));
+        assert_eq!(circle_intersection(&a, &b, 0.0, 0.0, 5.0), Some(0.5));
+
+        // Crossing twice, the exit furthest along wins
+        let (a, b) = (Waypoint::new(-10.0, 0.0, 0.0), Waypoint::new(10.0, 0.0, 0.0));
+        assert_eq!(circle_intersection(&a, &b, 0.0, 0.0, 5.0), Some(0.75));
+        assert_eq!(circle_intersection(&b, &a, 0.0, 0.0, 5.0), Some(0.75));
+    }
+
+    #[test]
+    fn circle_misses_segment() {
+        let (a, b) = (Waypoint::new(0.0, 0.0, 0.0), Waypoint::new(0.0, 2.0, 0.0));
+        // Segment wholly inside the circle
+        assert_eq!(circle_intersection(&a, &b, 0.0, 0.0, 5.0), None);
+        // Segment wholly outside it
+        assert_eq!(circle_intersection(&a, &b, 10.0, 0.0, 5.0), None);
+        // Zero-length segment
+        assert_eq!(circle_intersection(&a, &a, 0.0, 5.0, 5.0), None);
+    }
+
+    #[test]
+    fn drives_straight_along_a_straight_path() {
+        let path = straight(40);
+        let mut controller = PurePursuitController::new(&path, FollowPathParams::default());
+        let (left, right) = controller.step(&Pose::default(), Duration::from_millis(10)).unwrap();
+        assert!((left - 1.0).abs() < 1e-9 && (right - 1.0).abs() < 1e-9, "({left}, {right})");
+    }
+
+    #[test]
+    fn steers_towards_a_path_off_to_the_right() {
+        let path = Path::new((0..=40).map(|y| Waypoint::new(10.0, y as f64, 0.5)).collect());
+        let mut controller = PurePursuitController::new(&path, FollowPathParams::default());
+        let (left, right) = controller.step(&Pose::default(), Duration::from_millis(10)).unwrap();
+        assert!(left > right, "({left}, {right})");
+    }
+
+    #[test]
+    fn reversing_swaps_and_negates_the_sides() {
+        // Backing up the +y axis, with the path off to the robot's left (+x):
+        // the rear swings left by turning clockwise, so the right side leads
+        let path = Path::new((0..=40).map(|y| Waypoint::new(10.0, y as f64, 0.5)).collect());
+        let params = FollowPathParams { reversed: true, ..Default::default() };
+        let mut controller = PurePursuitController::new(&path, params);
+        let pose = Pose::from_inches(0.0, 0.0, 180.0);
+        let (left, right) = controller.step(&pose, Duration::from_millis(10)).unwrap();
+        assert!(left < 0.0 && right < 0.0, "({left}, {right})");
+        assert!(right < left, "({left}, {right})");
+
+        // The same geometry driven forwards gives the mirrored output
+        let mut forwards = PurePursuitController::new(&path, FollowPathParams::default());
+        let (f_left, f_right) = forwards.step(&Pose::default(), Duration::from_millis(10)).unwrap();
+        assert!((left + f_right).abs() < 1e-9 && (right + f_left).abs() < 1e-9);
+    }
+
+    #[test]
+    fn finishes_within_end_tolerance_of_the_last_point() {
+        let path = straight(40);
+        let mut controller = PurePursuitController::new(&path, FollowPathParams::default());
+        assert!(controller.step(&Pose::from_inches(0.0, 30.0, 0.0), Duration::from_millis(10)).is_some());
+        assert!(controller.step(&Pose::from_inches(0.0, 38.5, 0.0), Duration::from_millis(10)).is_none());
+    }
+
+    #[test]
+    fn finishes_once_the_last_point_is_nearest() {
+        let path = straight(40);
+        let params = FollowPathParams { end_tolerance: 0.0, ..Default::default() };
+        let mut controller = PurePursuitController::new(&path, params);
+        for y in [10.0, 20.0, 30.0] {
+            assert!(controller.step(&Pose::from_inches(0.0, y, 0.0), Duration::from_millis(10)).is_some());
+        }
+        // Past the end, off to the side
+        let pose = Pose::from_inches(5.0, 45.0, 0.0);
+        assert!(controller.step(&pose, Duration::from_millis(10)).is_none());
+        assert!(PurePursuitController::new(&Path::default(), params)
+            .step(&pose, Duration::from_millis(10))
+            .is_none());
+    }
+
+    #[test]
+    fn closest_point_does_not_jump_to_a_later_pass() {
+        // Out along y = 0 and back along y = 1
+        let mut points: Vec<Waypoint> = (0..=40).map(|x| Waypoint::new(x as f64, 0.0, 1.0)).collect();
+        points.extend((0..=40).rev().map(|x| Waypoint::new(x as f64, 1.0, 1.0)));
+        let path = Path::new(points);
+
+        let mut controller = PurePursuitController::new(&path, FollowPathParams::default());
+        // Nearer the return leg, but that is a whole lap further along
+        controller.update_closest(5.0, 0.8);
+        assert_eq!(controller.closest, 5);
+    }
+}
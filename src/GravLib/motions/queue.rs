@@ -0,0 +1,120 @@
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use core::time::Duration;
+
+use vexide::competition;
+
+use crate::GravLib::path::Path;
+use crate::GravLib::motions::{
+    chassis::Chassis,
+    motion_cancel_helper::cancel_epoch,
+    move_to_point::{move_to_point, MoveToPointParams, MoveToSettings},
+    move_to_pose::{move_to_pose, MoveToPoseParams},
+    pure_pursuit::{follow_path, FollowPathParams},
+    turn_to::{
+        swing_to_heading, swing_to_point, turn_to_heading, turn_to_point, LockedSide, TurnToParams,
+        TurnToSettings,
+    },
+};
+
+/// One queued motion and its arguments. See the function of the same name
+/// for what each does.
+pub enum Motion {
+    TurnToHeading { target: f64, timeout: Duration, params: TurnToParams },
+    TurnToPoint { x: f64, y: f64, timeout: Duration, params: TurnToParams },
+    SwingToHeading { target: f64, locked: LockedSide, timeout: Duration, params: TurnToParams },
+    SwingToPoint { x: f64, y: f64, locked: LockedSide, timeout: Duration, params: TurnToParams },
+    MoveToPoint { x: f64, y: f64, timeout: Duration, params: MoveToPointParams },
+    MoveToPose { x: f64, y: f64, theta: f64, timeout: Duration, params: MoveToPoseParams },
+    FollowPath { path: Path, timeout: Duration, params: FollowPathParams },
+}
+
+/// Runs motions back to back. A motion with a `min_speed` or
+/// `early_exit_range` hands its final output to the next one through the
+/// `Chassis`, so the robot flows from one to the next without stopping.
+pub struct MotionQueue {
+    chassis: Chassis,
+    turn_settings: TurnToSettings,
+    move_settings: MoveToSettings,
+    motions: VecDeque<Motion>,
+}
+
+impl MotionQueue {
+    pub fn new(chassis: Chassis, turn_settings: TurnToSettings, move_settings: MoveToSettings) -> Self {
+        Self {
+            chassis,
+            turn_settings,
+            move_settings,
+            motions: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, motion: Motion) -> &mut Self {
+        self.motions.push_back(motion);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.motions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.motions.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.motions.clear();
+    }
+
+    /// Runs every queued motion in order, then stops the drive. If the
+    /// competition mode changes part way or `cancel_all_motions` is called,
+    /// the rest of the queue is dropped.
+    pub async fn run(&mut self) {
+        let mode = competition::status().mode();
+        let epoch = cancel_epoch();
+
+        while let Some(motion) = self.motions.pop_front() {
+            self.run_motion(motion).await;
+
+            if competition::status().mode() != mode || cancel_epoch() != epoch {
+                self.motions.clear();
+                break;
+            }
+        }
+
+        // The last motion may have been chained into nothing
+        self.chassis.stop();
+    }
+
+    /// Runs a single motion now, with the queue's settings.
+    pub async fn run_motion(&mut self, motion: Motion) {
+        let chassis = &self.chassis;
+        let turn = &mut self.turn_settings;
+        let lateral = &mut self.move_settings;
+
+        match motion {
+            Motion::TurnToHeading { target, timeout, params } => {
+                turn_to_heading(chassis, target, timeout, params, turn).await
+            }
+            Motion::TurnToPoint { x, y, timeout, params } => {
+                turn_to_point(chassis, x, y, timeout, params, turn).await
+            }
+            Motion::SwingToHeading { target, locked, timeout, params } => {
+                swing_to_heading(chassis, target, locked, timeout, params, turn).await
+            }
+            Motion::SwingToPoint { x, y, locked, timeout, params } => {
+                swing_to_point(chassis, x, y, locked, timeout, params, turn).await
+            }
+            Motion::MoveToPoint { x, y, timeout, params } => {
+                move_to_point(chassis, x, y, timeout, params, lateral).await
+            }
+            Motion::MoveToPose { x, y, theta, timeout, params } => {
+                move_to_pose(chassis, x, y, theta, timeout, params, lateral).await
+            }
+            Motion::FollowPath { path, timeout, params } => {
+                follow_path(chassis, &path, timeout, params).await
+            }
+        }
+    }
+}
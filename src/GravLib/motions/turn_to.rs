@@ -1,28 +1,400 @@
-use crate::Gravlib::PID;
+use core::time::Duration;
 
-enum locked_side{
+#[cfg(target_vendor = "vex")]
+use uom::si::angle::degree;
+
+use crate::GravLib::{ExitCondition, PID};
+use crate::GravLib::hardware::BrakeMode;
+#[cfg(target_vendor = "vex")]
+use crate::GravLib::odom::pose::Pose;
+use crate::GravLib::motions::util::{constrain_speed, slew};
+use crate::GravLib::odom::pose::{normalise_degrees, wrap_degrees};
+#[cfg(target_vendor = "vex")]
+use crate::GravLib::motions::chassis::Chassis;
+
+/// Once the error the forced way round drops below this (degrees), the forced
+/// direction is released so the robot can correct small overshoots.
+const FORCED_DIRECTION_RELEASE: f64 = 30.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LockedSide {
     LEFT,
     RIGHT,
 }
 
-enum angular_direction{
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AngularDirection {
     CLOCKWISE,
     COUNTERCLOCKWISE,
 }
 
-struct turn_to_params{
-    config: Option<locked_side>,
-    direction: Option<angular_direction>,
-    maxSpeed: Option<f64>, // TODO - Default = 1
-    minSpeedL: Option<f64>, // TODO - Default = 0
-    slew: Option<f64>, // TODO - Default = 0
-    earlyExitRange: Option<f64>, // TODO - 0 degrees
+#[derive(Clone, Copy, Debug)]
+pub struct TurnToParams {
+    /// How the locked side holds still during a swing.
+    pub locked_brake: BrakeMode,
+    /// Forces the turn to go one way round. `None` takes the shortest way.
+    pub direction: Option<AngularDirection>,
+    /// Fraction of full power, 0.0 to 1.0.
+    pub max_speed: f64,
+    /// Fraction of full power, 0.0 to 1.0. Non-zero values make the motion
+    /// exit as soon as it crosses the target, for chaining.
+    pub min_speed: f64,
+    /// Largest increase in power per 10ms iteration. 0 disables slew.
+    pub slew: f64,
+    /// Exit once within this many degrees of the target. 0 disables it.
+    pub early_exit_range: f64,
+    /// For `turn_to_point`: face the point with the front of the robot, or
+    /// with the back if `false`.
+    pub forwards: bool,
+}
+
+impl Default for TurnToParams {
+    fn default() -> Self {
+        Self {
+            locked_brake: BrakeMode::Hold,
+            direction: None,
+            max_speed: 1.0,
+            min_speed: 0.0,
+            slew: 0.0,
+            early_exit_range: 0.0,
+            forwards: true,
+        }
+    }
+}
+
+pub struct TurnToSettings {
+    /// Takes heading error in degrees, outputs a fraction of full power.
+    pub pid: PID,
+    /// When the turn is finished, checked against the heading error in
+    /// degrees.
+    pub exit: ExitCondition,
 }
 
-struct turn_to_settings{
-    pid: PID,
+/// The heading control law, with no hardware or timing of its own. Feed it
+/// the current heading every iteration; it returns the power for each side,
+/// or `None` once the turn is finished. This is what lets turns be checked
+/// against `sim::Simulation` on a host.
+pub struct TurnController<'a> {
+    target: f64,
+    params: TurnToParams,
+    settings: &'a mut TurnToSettings,
+
+    prev_power: f64,
+    prev_error: Option<f64>,
+    prev_forced_error: Option<f64>,
+    released: bool,
+}
+
+impl<'a> TurnController<'a> {
+    /// `target` is a field heading in degrees.
+    pub fn new(target: f64, params: TurnToParams, settings: &'a mut TurnToSettings) -> Self {
+        settings.pid.reset();
+        settings.exit.reset();
+        Self {
+            target: normalise_degrees(target),
+            params,
+            settings,
+            prev_power: 0.0,
+            prev_error: None,
+            prev_forced_error: None,
+            released: params.direction.is_none(),
+        }
+    }
+
+    /// Starts slewing from the turning power of the previous motion's output,
+    /// rather than from rest.
+    pub fn carry(&mut self, left: f64, right: f64) {
+        self.prev_power = (left - right) / 2.0;
+    }
+
+    /// Retargets the turn without resetting its state, for targets that move
+    /// while the robot turns.
+    pub fn set_target(&mut self, target: f64) {
+        self.target = normalise_degrees(target);
+    }
+
+    /// Signed heading error the PID acts on, honouring a forced direction.
+    fn error(&mut self, heading: f64) -> f64 {
+        let shortest = wrap_degrees(self.target - heading);
+        if self.released {
+            return shortest;
+        }
+
+        let forced = match self.params.direction {
+            Some(AngularDirection::CLOCKWISE) => normalise_degrees(self.target - heading),
+            Some(AngularDirection::COUNTERCLOCKWISE) => -normalise_degrees(heading - self.target),
+            None => return shortest,
+        };
+
+        // Release near the target, or if a fast step jumped clean past it,
+        // which shows up as the forced error wrapping round
+        let crossed = self.prev_forced_error.is_some_and(|prev| (forced - prev).abs() > 180.0);
+        self.prev_forced_error = Some(forced);
+        if forced.abs() < FORCED_DIRECTION_RELEASE || crossed {
+            self.released = true;
+            return shortest;
+        }
+        forced
+    }
+
+    /// `heading` is the current field heading in degrees and `dt` the time
+    /// since the previous call.
+    pub fn step(&mut self, heading: f64, dt: Duration) -> Option<(f64, f64)> {
+        let power = self.step_power(heading, dt)?;
+        // Clockwise is positive, so the left side drives forwards
+        Some((power, -power))
+    }
+
+    /// Same as `step`, but returns the single signed turning power rather
+    /// than splitting it between sides.
+    pub(crate) fn step_power(&mut self, heading: f64, dt: Duration) -> Option<f64> {
+        let error = self.error(heading);
+
+        // Close enough to hand over to the next motion
+        if self.params.early_exit_range > 0.0 && error.abs() < self.params.early_exit_range {
+            return None;
+        }
+
+        // With a minimum speed the robot can't settle, so stop once it crosses the target
+        if let Some(prev) = self.prev_error {
+            if self.params.min_speed != 0.0 && self.released && prev.signum() != error.signum() {
+                return None;
+            }
+        }
+        self.prev_error = Some(error);
+
+        if self.settings.exit.update(error, dt) {
+            return None;
+        }
+
+        let raw = self.settings.pid.update_with_dt(error as f32, dt) as f64;
+        let power = constrain_speed(raw, self.params.max_speed, self.params.min_speed);
+        let power = slew(power, self.prev_power, self.params.slew);
+        self.prev_power = power;
+
+        Some(power)
+    }
+}
+
+/// Turns in place to face `target`, a field heading in degrees.
+///
+/// Ends when the heading settles, the early exit range is reached, `timeout`
+/// passes, or the motion is cancelled.
+#[cfg(target_vendor = "vex")]
+pub async fn turn_to_heading(
+    chassis: &Chassis,
+    target: f64,
+    timeout: Duration,
+    params: TurnToParams,
+    settings: &mut TurnToSettings,
+) {
+    let controller = TurnController::new(target, params, settings);
+    run_turn(chassis, controller, None, timeout, |_| None).await;
+}
+
+/// Turns in place to face the field point `(x, y)`, in inches. The target
+/// heading is recomputed from the live pose every iteration, so it stays
+/// correct if the robot gets pushed while turning.
+///
+/// Set `params.forwards` to `false` to face the point with the back instead.
+#[cfg(target_vendor = "vex")]
+pub async fn turn_to_point(
+    chassis: &Chassis,
+    x: f64,
+    y: f64,
+    timeout: Duration,
+    params: TurnToParams,
+    settings: &mut TurnToSettings,
+) {
+    let point = Pose::from_inches(x, y, 0.0);
+    let forwards = params.forwards;
+
+    let initial = facing(&chassis.pose(), &point, forwards);
+    let controller = TurnController::new(initial, params, settings);
+    run_turn(chassis, controller, None, timeout, |pose| Some(facing(pose, &point, forwards))).await;
 }
 
-fn turnTo(target: f64, timeout: f64, params: turn_to_params, turn_to_settings: turn_to_settings) {
-    
-}
\ No newline at end of file
+/// Swings to `target`, a field heading in degrees, by holding the `locked`
+/// side still with `params.locked_brake` and driving only the other side.
+/// The robot pivots about the locked wheels.
+#[cfg(target_vendor = "vex")]
+pub async fn swing_to_heading(
+    chassis: &Chassis,
+    target: f64,
+    locked: LockedSide,
+    timeout: Duration,
+    params: TurnToParams,
+    settings: &mut TurnToSettings,
+) {
+    let controller = TurnController::new(target, params, settings);
+    run_turn(chassis, controller, Some(locked), timeout, |_| None).await;
+}
+
+/// Swings to face the field point `(x, y)`, in inches, pivoting about the
+/// `locked` side. Like `turn_to_point`, the target follows the live pose and
+/// `params.forwards` picks which end of the robot faces the point.
+#[cfg(target_vendor = "vex")]
+pub async fn swing_to_point(
+    chassis: &Chassis,
+    x: f64,
+    y: f64,
+    locked: LockedSide,
+    timeout: Duration,
+    params: TurnToParams,
+    settings: &mut TurnToSettings,
+) {
+    let point = Pose::from_inches(x, y, 0.0);
+    let forwards = params.forwards;
+
+    let initial = facing(&chassis.pose(), &point, forwards);
+    let controller = TurnController::new(initial, params, settings);
+    run_turn(chassis, controller, Some(locked), timeout, |pose| {
+        Some(facing(pose, &point, forwards))
+    })
+    .await;
+}
+
+/// Heading (degrees) the robot at `pose` needs to face `point`.
+#[cfg(target_vendor = "vex")]
+pub(crate) fn facing(pose: &Pose, point: &Pose, forwards: bool) -> f64 {
+    let heading = pose.angle_to(point).get::<degree>();
+    if forwards { heading } else { normalise_degrees(heading + 180.0) }
+}
+
+/// Drives `controller` until it finishes, `timeout` passes, or the motion is
+/// cancelled. `retarget` may return a new target heading from the live pose.
+/// With a `locked` side the turn is a swing; otherwise it is a point turn.
+///
+/// Picks up from a chained previous motion, and leaves the drive running for
+/// the next one if `min_speed` or `early_exit_range` is set, until
+/// `HANDOFF_TIMEOUT` passes.
+#[cfg(target_vendor = "vex")]
+async fn run_turn(
+    chassis: &Chassis,
+    mut controller: TurnController<'_>,
+    locked: Option<LockedSide>,
+    timeout: Duration,
+    mut retarget: impl FnMut(&Pose) -> Option<f64>,
+) {
+    let brake_mode = controller.params.locked_brake;
+    let chain = controller.params.min_speed > 0.0 || controller.params.early_exit_range > 0.0;
+    if let Some((left, right)) = chassis.take_handoff() {
+        controller.carry(left, right);
+    }
+
+    // As with driving, a motor that fails to brake doesn't stop the others
+    let _ = match locked {
+        Some(LockedSide::LEFT) => chassis.left.lock().brake(brake_mode),
+        Some(LockedSide::RIGHT) => chassis.right.lock().brake(brake_mode),
+        None => Ok(()),
+    };
+
+    let step = |pose: &Pose, dt: Duration| {
+        if let Some(target) = retarget(pose) {
+            controller.set_target(target);
+        }
+        let power = controller.step_power(pose.theta().get::<degree>(), dt)?;
+
+        // Clockwise is positive. Pivoting about the left side, the right side
+        // has to drive backwards to turn clockwise; about the right, the left
+        // side drives forwards.
+        Some(match locked {
+            None => (power, -power),
+            Some(LockedSide::LEFT) => (0.0, -power),
+            Some(LockedSide::RIGHT) => (power, 0.0),
+        })
+    };
+
+    // Only drive the free side, so the locked one keeps its brake
+    chassis
+        .run_with(timeout, chain, step, |left, right| match locked {
+            None => chassis.drive(left, right),
+            Some(LockedSide::LEFT) => chassis.drive_right(right),
+            Some(LockedSide::RIGHT) => chassis.drive_left(left),
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GravLib::hardware::{Actuator, HeadingSource};
+    use crate::GravLib::sim::{Side, SimConfig, Simulation};
+
+    const DT: Duration = Duration::from_millis(10);
+
+    fn settings() -> TurnToSettings {
+        TurnToSettings {
+            pid: PID::new(0.02, 0.0, 0.0, 0.0, false),
+            exit: ExitCondition::range(1.0, Duration::from_millis(100)),
+        }
+    }
+
+    fn forced(direction: AngularDirection) -> TurnToParams {
+        TurnToParams { direction: Some(direction), ..Default::default() }
+    }
+
+    #[test]
+    fn forced_direction_goes_the_long_way() {
+        let mut settings = settings();
+        let mut controller = TurnController::new(10.0, forced(AngularDirection::COUNTERCLOCKWISE), &mut settings);
+        let (left, right) = controller.step(0.0, DT).unwrap();
+        assert!(left < 0.0 && right > 0.0, "turned clockwise: ({left}, {right})");
+    }
+
+    #[test]
+    fn forced_turn_reaches_target_from_the_forced_side() {
+        let mut settings = settings();
+        let mut controller = TurnController::new(10.0, forced(AngularDirection::COUNTERCLOCKWISE), &mut settings);
+
+        // Spins at up to 360°/s
+        let mut heading: f64 = 0.0;
+        let mut furthest: f64 = 0.0;
+        while let Some(power) = controller.step_power(heading, DT) {
+            heading = normalise_degrees(heading + power * 360.0 * DT.as_secs_f64());
+            furthest = furthest.max(wrap_degrees(0.0 - heading));
+        }
+
+        assert!(furthest > 170.0, "only went {furthest}° the long way");
+        assert!(wrap_degrees(10.0 - heading).abs() < 1.0, "settled at {heading}");
+    }
+
+    #[test]
+    fn turns_the_simulated_drivetrain_onto_target() {
+        let mut sim = Simulation::new(SimConfig::default(), DT);
+        let mut left = [sim.motor(Side::Left), sim.motor(Side::Left)];
+        let mut right = [sim.motor(Side::Right), sim.motor(Side::Right)];
+        let imu = sim.imu();
+
+        let mut settings = TurnToSettings {
+            pid: PID::new(0.015, 0.0, 0.001, 0.0, false),
+            exit: ExitCondition::range(1.0, Duration::from_millis(100)),
+        };
+        let mut controller = TurnController::new(270.0, TurnToParams::default(), &mut settings);
+
+        // The short way from 0° to 270° is anticlockwise
+        let mut lowest = 0.0_f64;
+        let mut ticks = 0;
+        while let Some((l, r)) = controller.step(imu.heading().unwrap(), DT) {
+            left.iter_mut().for_each(|m| m.set_voltage(l * 12.0).unwrap());
+            right.iter_mut().for_each(|m| m.set_voltage(r * 12.0).unwrap());
+            sim.tick();
+            lowest = lowest.min(wrap_degrees(imu.heading().unwrap()));
+            ticks += 1;
+            assert!(ticks < 500, "never settled, at {}", imu.heading().unwrap());
+        }
+
+        let heading = imu.heading().unwrap();
+        assert!(wrap_degrees(270.0 - heading).abs() < 1.0, "settled at {heading}");
+        assert!(lowest > -100.0, "went the long way or overshot to {lowest}");
+    }
+
+    #[test]
+    fn forced_direction_releases_after_jumping_past_target() {
+        let mut settings = settings();
+        let mut controller = TurnController::new(90.0, forced(AngularDirection::CLOCKWISE), &mut settings);
+        assert!(controller.step_power(40.0, DT).unwrap() > 0.0);
+        // Overshot by 10°; going round again would be 350°
+        assert!(controller.step_power(100.0, DT).unwrap() < 0.0);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::GravLib::odom::pose::Pose;
+
+/// Inside this distance (inches) of the target, point and pose motions stop
+/// steering at it, so the robot doesn't circle the point trying to face it.
+pub const CLOSE_DISTANCE: f64 = 7.5;
+
+/// Limits how fast `target` may grow away from `current`, by `max_change` per
+/// iteration. Slowing down is never limited. A `max_change` of 0 disables it.
+pub fn slew(target: f64, current: f64, max_change: f64) -> f64 {
+    if max_change <= 0.0 || target.abs() <= current.abs() && target.signum() == current.signum() {
+        return target;
+    }
+
+    current + (target - current).clamp(-max_change, max_change)
+}
+
+/// Clamps `power` to `max_speed` and, unless it is exactly zero, raises it to
+/// at least `min_speed`. Both are fractions of full power.
+pub fn constrain_speed(power: f64, max_speed: f64, min_speed: f64) -> f64 {
+    let power = power.clamp(-max_speed, max_speed);
+    if power != 0.0 && power.abs() < min_speed {
+        min_speed * power.signum()
+    } else {
+        power
+    }
+}
+
+/// Mixes lateral and angular power into `(left, right)`, scaling both down
+/// together if either side would exceed `max_speed` so the turn ratio holds.
+pub fn desaturate(lateral: f64, angular: f64, max_speed: f64) -> (f64, f64) {
+    let ratio = (lateral.abs() + angular.abs()) / max_speed;
+    if ratio > 1.0 {
+        ((lateral + angular) / ratio, (lateral - angular) / ratio)
+    } else {
+        (lateral + angular, lateral - angular)
+    }
+}
+
+/// Signed curvature (1/inches) of the arc that leaves `pose` along its heading
+/// and passes through the field point `(x, y)`. Positive curves clockwise.
+pub fn arc_curvature(pose: &Pose, x: f64, y: f64) -> f64 {
+    let (px, py, theta) = pose.get_position();
+    let (sin_h, cos_h) = (libm::sin(theta.to_radians()), libm::cos(theta.to_radians()));
+
+    // The point in the robot frame: +x right, +y forward
+    let (dx, dy) = (x - px, y - py);
+    let local_x = dx * cos_h - dy * sin_h;
+    let local_y = dx * sin_h + dy * cos_h;
+
+    let d2 = local_x * local_x + local_y * local_y;
+    if d2 == 0.0 { 0.0 } else { 2.0 * local_x / d2 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desaturate_passes_through_within_max_speed() {
+        assert_eq!(desaturate(0.5, 0.25, 1.0), (0.75, 0.25));
+        assert_eq!(desaturate(-0.5, 0.5, 1.0), (0.0, -1.0));
+    }
+
+    #[test]
+    fn desaturate_keeps_the_turn_ratio() {
+        let (left, right) = desaturate(1.0, 0.5, 1.0);
+        assert!((left - 1.0).abs() < 1e-12);
+        // (1.5, 0.5) scaled by the same factor
+        assert!((right - 1.0 / 3.0).abs() < 1e-12);
+
+        let (left, right) = desaturate(-0.6, -0.6, 0.5);
+        assert!((left + 0.5).abs() < 1e-12);
+        assert!(right.abs() < 1e-12);
+    }
+
+    #[test]
+    fn desaturate_turns_in_place_at_full_power() {
+        assert_eq!(desaturate(0.0, 2.0, 1.0), (1.0, -1.0));
+        assert_eq!(desaturate(0.0, -1.0, 1.0), (-1.0, 1.0));
+    }
+}
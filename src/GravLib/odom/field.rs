@@ -0,0 +1,87 @@
+// field.rs
+//
+// Field-frame coordinates for a standard 12' x 12' VEX field, in inches with
+// the origin at the centre of the field. +y points from the red alliance wall
+// towards the blue alliance wall and +x is to the right as seen from red.
+//
+// Tiles are named like a chessboard: columns 'A'-'F' from left to right and
+// rows 1-6 from the red wall to the blue wall, both as seen from red.
+use crate::GravLib::odom::pose::Pose;
+
+pub const TILE_SIZE: f64 = 24.0;
+pub const FIELD_SIZE: f64 = 6.0 * TILE_SIZE;
+
+/// Centre of the named tile, facing +y (away from red).
+/// Returns `None` if the tile is off the field.
+pub fn tile(column: char, row: u8) -> Option<Pose> {
+    let column = column.to_ascii_uppercase();
+    if !('A'..='F').contains(&column) || !(1..=6).contains(&row) {
+        return None;
+    }
+
+    let half = FIELD_SIZE / 2.0;
+    let x = -half + TILE_SIZE * ((column as u8 - b'A') as f64 + 0.5);
+    let y = -half + TILE_SIZE * ((row - 1) as f64 + 0.5);
+    Some(Pose::from_inches(x, y, 0.0))
+}
+
+/// Common starting positions, named from the point of view of each
+/// alliance's drivers. Each is the centre of a tile against the alliance
+/// wall, facing into the field.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StartTile {
+    RedLeft,
+    RedRight,
+    BlueLeft,
+    BlueRight,
+}
+
+impl StartTile {
+    pub fn pose(self) -> Pose {
+        let (column, row, heading) = match self {
+            StartTile::RedLeft   => ('B', 1, 0.0),
+            StartTile::RedRight  => ('E', 1, 0.0),
+            // Blue drivers look down -y, so their left is +x
+            StartTile::BlueLeft  => ('E', 6, 180.0),
+            StartTile::BlueRight => ('B', 6, 180.0),
+        };
+
+        // Tiles in the table above are always on the field
+        let centre = tile(column, row).unwrap();
+        let (x, y, _) = centre.get_position();
+        Pose::from_inches(x, y, heading)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn red_left() {
+        assert_eq!(StartTile::RedLeft.pose().get_position(), (-36.0, -60.0, 0.0));
+    }
+
+    #[test]
+    fn red_right() {
+        assert_eq!(StartTile::RedRight.pose().get_position(), (36.0, -60.0, 0.0));
+    }
+
+    #[test]
+    fn blue_left() {
+        assert_eq!(StartTile::BlueLeft.pose().get_position(), (36.0, 60.0, 180.0));
+    }
+
+    #[test]
+    fn blue_right() {
+        assert_eq!(StartTile::BlueRight.pose().get_position(), (-36.0, 60.0, 180.0));
+    }
+
+    #[test]
+    fn tiles_outside_the_field_are_rejected() {
+        assert_eq!(tile('a', 1), tile('A', 1));
+        assert!(tile('G', 1).is_none());
+        assert!(tile('A', 0).is_none());
+        assert!(tile('A', 7).is_none());
+    }
+}
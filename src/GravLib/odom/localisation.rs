@@ -1,94 +1,82 @@
 use core::time::Duration;
 
-use vexide::devices::adi::digital;
-use vexide::{
-    devices::smart::InertialSensor,
-};
-
 use alloc::{sync::Arc, vec::Vec};
+#[cfg(target_vendor = "vex")]
 use vexide::io::println;
 use spin::Mutex;
 use alloc::vec;
 
 
-use uom::{si::f64::Angle, ConstZero};
+use uom::si::{
+    angle::{degree, radian},
+    f64::{Angle, Length},
+    length::inch,
+};
 
 use libm;
 
-use vexide::devices::math::Point2;
-use vexide::prelude::*;
-use vexide::devices::display::{self, *};
-
+use crate::GravLib::hardware::{Clock, SystemClock};
+use crate::GravLib::odom::pose::{rotate_vector, wrap_angle, Pose};
 use crate::GravLib::odom::sensors::{TrackingWheel, Sensors};
 
-use alloc::format;
-
-pub struct Pose {
-    x: f64,
-    y: f64,
-    theta: f64, // in degrees
-}
-
-impl Pose {
-    pub fn new() -> Self {
-        Self {
-            x: 0.0,
-            y: 0.0,
-            theta: 0.0,
-        }
-    }
-
-    pub fn get_position(&self) -> (f64, f64, f64) {
-        (self.x, self.y, self.theta)
-    }
-}
-
 pub struct Localisation {
     pub sensors: Arc<Mutex<Sensors>>,
     pub m_pose: Arc<Mutex<Pose>>,
+    clock: Arc<dyn Clock>,
+    m_last_update: Option<Duration>,
+    /// Added to the IMU heading to get the pose heading, in degrees.
+    m_heading_offset: f64,
     prev_vertical_total: Vec<f64>,
     prev_horizontal_total: Vec<f64>,
 }
 
-fn calculate_wheel_heading(wheels: &Vec<Arc<Mutex<TrackingWheel>>>) -> f64 {
+#[allow(dead_code)]
+fn calculate_wheel_heading(wheels: &[Arc<Mutex<TrackingWheel>>]) -> f64 {
     // if not enough wheels
     if wheels.len() < 2 {
         return 0.0; // or some default value
     }
 
     // get data
-    let distance1 = wheels[0].lock().get_distance_travelled();
-    let distance2 = wheels[1].lock().get_distance_travelled();
+    let (Some(distance1), Some(distance2)) = (
+        wheels[0].lock().get_distance_travelled(),
+        wheels[1].lock().get_distance_travelled(),
+    ) else {
+        return 0.0;
+    };
 
     let offset1 = wheels[0].lock().get_offset();
     let offset2 = wheels[1].lock().get_offset();
 
     // TODO - Add error logic for:
         // [] - equal offsets
-        // [] - error wheel readings
 
     // calculate heading
     ((distance1 - distance2) / (offset1 - offset2)) + 90.0
 }
 
-//TODO - Add thing to consider faulty sensors
+#[allow(dead_code)]
 fn find_lateral_delta(sensors: Vec<Arc<Mutex<TrackingWheel>>>) -> f64 {
     let mut data_vec = Vec::new();
-    for i in 0..sensors.len() {
-        let sensor = sensors[i].lock();
-        let data = sensor.get_distance_travelled();
-        // TODO - Handle fault sensor.
-        data_vec.push(data);
+    for sensor in &sensors {
+        let sensor = sensor.lock();
+        // Wheels that can't be read are left out
+        if let Some(data) = sensor.get_distance_travelled() {
+            data_vec.push(data);
+        }
     }
 
-    return if !data_vec.is_empty() {
+    if !data_vec.is_empty() {
         data_vec.iter().sum::<f64>() / data_vec.len() as f64
     } else {
         0.0 // Return 0.0 if no data was found
-    };
+    }
 }
 
 /// Compute the robot’s local (Δx, Δy) given wheel deltas & offsets and a rotation Δθ.
+/// Offsets follow `TrackingWheel::new`: a clockwise turn moves a wheel right
+/// of centre backwards and a wheel ahead of centre to the right, and both
+/// are taken back out here.
 fn compute_local_position(
     delta_theta: f64,
     vertical_delta: f64,
@@ -108,21 +96,21 @@ fn compute_local_position(
         let inv_theta = 1.0 / delta_theta;
         let radius = (
             lateral_deltas.0 * inv_theta + lateral_offsets.0,
-            lateral_deltas.1 * inv_theta + lateral_offsets.1,
+            lateral_deltas.1 * inv_theta - lateral_offsets.1,
         );
         (factor * radius.0, factor * radius.1)
     }
 }
 
 /// Reject outliers from the deltas and return the average of the remaining values.
-fn reject_outliers_and_average(deltas: &Vec<f64>, threshold: f64) -> f64 {
+fn reject_outliers_and_average(deltas: &[f64], threshold: f64) -> f64 {
     if deltas.is_empty() {
         return 0.0;
     }
 
     let mean = deltas.iter().copied().sum::<f64>() / deltas.len() as f64;
-    let variance = deltas.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / deltas.len() as f64;
-    let std_dev = variance.sqrt();
+    let variance = deltas.iter().map(|d| (d - mean) * (d - mean)).sum::<f64>() / deltas.len() as f64;
+    let std_dev = libm::sqrt(variance);
 
     let filtered: Vec<f64> = deltas
         .iter()
@@ -140,17 +128,32 @@ fn reject_outliers_and_average(deltas: &Vec<f64>, threshold: f64) -> f64 {
 
 impl Localisation {
     pub fn new(sensors: Arc<Mutex<Sensors>>) -> Self {
+        Self::with_clock(sensors, Arc::new(SystemClock::new()))
+    }
+
+    /// Same as `new`, but timestamps updates with the given clock instead of
+    /// the Brain's. Pair with a `sim::ManualClock` to replay traces
+    /// deterministically.
+    pub fn with_clock(sensors: Arc<Mutex<Sensors>>, clock: Arc<dyn Clock>) -> Self {
         // Pre‑allocate space to store the last total for each wheel
         let num_v = sensors.lock().vertical_wheels.len();
         let num_h = sensors.lock().horizontal_wheels.len();
         Self {
             sensors,
-            m_pose: Arc::new(Mutex::new(Pose::new())),
+            m_pose: Arc::new(Mutex::new(Pose::default())),
+            clock,
+            m_last_update: None,
+            m_heading_offset: 0.0,
             prev_vertical_total: vec![0.0; num_v],
             prev_horizontal_total: vec![0.0; num_h],
         }
     }
 
+    /// Clock time of the most recent pose update, if any.
+    pub fn last_update(&self) -> Option<Duration> {
+        self.m_last_update
+    }
+
     pub async fn calibrate(&mut self, calibrate_imu: bool) {
         if calibrate_imu {
             println!("Calibrating IMU...");
@@ -168,107 +171,285 @@ impl Localisation {
             w.lock().reset();
         });
         println!("All sensors reset.");
-        
+
+        // Calibration re-zeroes the IMU, so line its heading back up with the pose
+        let pose = self.pose();
+        self.set_pose(pose);
+    }
+
+    pub fn pose(&self) -> Pose {
+        *self.m_pose.lock()
     }
 
-    pub fn update(&mut self, display: &mut Display) {
-        loop {
-            // 1. Read *deltas* from each wheel
-            let mut vertical_deltas = Vec::new();
-            let mut horizontal_deltas = Vec::new();
-
-            {
-                let s = self.sensors.lock();
-
-                // --- Vertical wheels ---
-                for (i, w) in s.vertical_wheels.iter().enumerate() {
-                    let total = w.lock().get_distance_travelled();
-                    let delta = total - self.prev_vertical_total[i];
-                    self.prev_vertical_total[i] = total;
-                    vertical_deltas.push(delta);
-                }
-
-                // --- Horizontal wheels ---
-                for (i, w) in s.horizontal_wheels.iter().enumerate() {
-                    let total = w.lock().get_distance_travelled();
-                    let delta = total - self.prev_horizontal_total[i];
-                    self.prev_horizontal_total[i] = total;
-                    horizontal_deltas.push(delta);
-                }
+    /// Overrides the current pose, e.g. with a `StartTile` at the start of
+    /// autonomous. The IMU itself is left alone; the offset between its
+    /// heading and the pose heading is adjusted so later updates carry on
+    /// from `pose`.
+    pub fn set_pose(&mut self, pose: Pose) {
+        let pose = pose.normalised();
+
+        match self.sensors.lock().imu.lock().heading() {
+            Some(imu_heading) => {
+                self.m_heading_offset = pose.theta().get::<degree>() - imu_heading;
             }
+            None => println!("IMU WENT WRONG!! Heading offset not updated."),
+        }
 
-            // Reject outliers and average the rest
-            let vertical_delta = reject_outliers_and_average(&vertical_deltas, 1.5);
-            let horizontal_delta = reject_outliers_and_average(&horizontal_deltas, 1.5);
+        // Anything the wheels did before now belongs to the old pose
+        self.resync();
+        *self.m_pose.lock() = pose;
+    }
 
+    /// Moves the pose back to the origin, facing +y.
+    pub fn reset_pose(&mut self) {
+        self.set_pose(Pose::default());
+    }
 
-            // 2. Correctly pick up theta and *assign* it
-            let mut theta = 0.0;
-            if let Ok(imu_heading) = self.sensors.lock().imu.lock().heading() {
-                theta = imu_heading; 
-            } else {
-                println!("IMU WENT WRONG!!");
+    /// Re-reads every wheel as the new baseline, so whatever the wheels did
+    /// since the last step is not integrated into the pose. A wheel that
+    /// can't be read keeps its old baseline.
+    pub fn resync(&mut self) {
+        let s = self.sensors.lock();
+        for (i, w) in s.vertical_wheels.iter().enumerate() {
+            if let Some(total) = w.lock().get_distance_travelled() {
+                self.prev_vertical_total[i] = total;
+            }
+        }
+        for (i, w) in s.horizontal_wheels.iter().enumerate() {
+            if let Some(total) = w.lock().get_distance_travelled() {
+                self.prev_horizontal_total[i] = total;
             }
+        }
+    }
 
-            // 3. Convert heading difference into *radians* for the chord formula
-            let old_theta = self.m_pose.lock().theta;
-            let delta_theta_deg = theta - old_theta;
-            let delta_theta_rad = delta_theta_deg.to_radians();
-
-            // 4. Compute local Δx/Δy using the chord formula
-            let vertical_offset   = self.sensors.lock().vertical_wheels[0].lock().get_offset();
-            let horizontal_offset = self.sensors.lock().horizontal_wheels[0].lock().get_offset();
-
-            println!("Delta θ: {:}°", delta_theta_rad);
-            let (delta_x, delta_y) = if delta_theta_rad.abs() == 0.0 {
-                // straight line
-                // println!("STRAIGHT");
-                (horizontal_delta, vertical_delta)  // Swapped: X gets horizontal, Y gets vertical
-            } else {
-                let factor = 2.0 * libm::sin(delta_theta_rad * 0.5);
-                let inv_theta = 1.0 / delta_theta_rad;
-                let r_x = horizontal_delta * inv_theta + horizontal_offset;  // X uses horizontal wheel
-                let r_y = vertical_delta   * inv_theta + vertical_offset;    // Y uses vertical wheel  
-                (factor * r_x, factor * r_y)
-            };
-
-            // 5. Rotate into the global frame
-            let mid_heading = (old_theta + delta_theta_deg * 0.5).to_radians();
-            let cos_h = libm::cos(mid_heading);
-            let sin_h = libm::sin(mid_heading);
-            let global_dx = delta_x * cos_h + delta_y * sin_h;
-            let global_dy = delta_x * sin_h + delta_y * cos_h;
-
-            // 6. Update your pose
-            {
-                let mut pose = self.m_pose.lock();
-                pose.x     += global_dx;
-                pose.y     += global_dy;
-                pose.theta  = theta;
-                // println!(
-                //   "Pose → x: {:+.4}, y: {:+.4}, θ: {:.2}°",
-                //   pose.x, pose.y, pose.theta
-                // );
+    /// Reads the sensors once and advances the pose by a single step.
+    /// Scheduling is left to the caller, see `OdometryTask`.
+    pub fn update(&mut self) {
+        // 1. Read *deltas* from each wheel. A wheel that can't be read is
+        // skipped and keeps its previous total, so its travel is counted
+        // once it reads again instead of being lost.
+        let mut vertical_deltas = Vec::new();
+        let mut horizontal_deltas = Vec::new();
+
+        let (vertical_offset, horizontal_offset) = {
+            let s = self.sensors.lock();
+
+            // --- Vertical wheels ---
+            for (i, w) in s.vertical_wheels.iter().enumerate() {
+                let Some(total) = w.lock().get_distance_travelled() else {
+                    continue;
+                };
+                let delta = total - self.prev_vertical_total[i];
+                self.prev_vertical_total[i] = total;
+                vertical_deltas.push(delta);
             }
 
-            let pose = self.m_pose.lock();
-            let (x, y, theta) = pose.get_position();
-            drop(pose);
-
-            display.set_render_mode(RenderMode::DoubleBuffered);
-            display.erase(Rgb::new(0, 0, 0));
-            display.draw_text(
-                &Text::new(
-                    &format!("X: {:+.4}\nY: {:+.4}\nθ: {:.2}°", x, y, theta),
-                    Font::new(FontSize::MEDIUM, FontFamily::Monospace),
-                    Point2::<i16>::from([10, 10]),
-                ),
-                Rgb::new(255, 255, 255),
-                Some(Rgb::new(0, 0, 0)),
-            );
-
-            // 7. Sleep until next tick
-            vexide::time::sleep(Duration::from_millis(10));
+            // --- Horizontal wheels ---
+            for (i, w) in s.horizontal_wheels.iter().enumerate() {
+                let Some(total) = w.lock().get_distance_travelled() else {
+                    continue;
+                };
+                let delta = total - self.prev_horizontal_total[i];
+                self.prev_horizontal_total[i] = total;
+                horizontal_deltas.push(delta);
+            }
+
+            (
+                s.vertical_wheels.first().map_or(0.0, |w| w.lock().get_offset()),
+                s.horizontal_wheels.first().map_or(0.0, |w| w.lock().get_offset()),
+            )
+        };
+
+        // Reject outliers and average the rest
+        let vertical_delta = reject_outliers_and_average(&vertical_deltas, 1.5);
+        let horizontal_delta = reject_outliers_and_average(&horizontal_deltas, 1.5);
+
+        // 2. Read the heading, holding the last one if the IMU drops out
+        let old_pose = *self.m_pose.lock();
+        let heading = match self.sensors.lock().imu.lock().heading() {
+            Some(heading) => heading + self.m_heading_offset,
+            None => {
+                println!("IMU WENT WRONG!!");
+                old_pose.theta().get::<degree>()
+            }
+        };
+
+        // 3. Integrate
+        let new_pose = integrate(
+            &old_pose,
+            heading,
+            vertical_delta,
+            horizontal_delta,
+            vertical_offset,
+            horizontal_offset,
+        );
+        *self.m_pose.lock() = new_pose;
+        self.m_last_update = Some(self.clock.now());
+    }
+}
+
+/// Advances `pose` by one odometry step. `heading` is the new field heading in
+/// degrees (IMU plus offset); the deltas are wheel travel since the previous step.
+///
+/// This touches no hardware, so recorded traces can be replayed through it.
+pub fn integrate(
+    pose: &Pose,
+    heading: f64,
+    vertical_delta: f64,
+    horizontal_delta: f64,
+    vertical_offset: f64,
+    horizontal_offset: f64,
+) -> Pose {
+    let old_theta = pose.theta();
+    let new_theta = Angle::new::<degree>(heading);
+
+    // Heading difference, taking the short way across the 0/360 seam
+    let delta_theta = wrap_angle(new_theta - old_theta);
+
+    // Local displacement using the chord formula
+    let (delta_y, delta_x) = compute_local_position(
+        delta_theta.get::<radian>(),
+        vertical_delta,
+        horizontal_delta,
+        vertical_offset,
+        horizontal_offset,
+    );
+
+    // Rotate into the global frame at the average heading over the step
+    let (global_dx, global_dy) = rotate_vector(
+        Length::new::<inch>(delta_x),
+        Length::new::<inch>(delta_y),
+        old_theta + delta_theta / 2.0,
+    );
+
+    Pose::new(pose.x() + global_dx, pose.y() + global_dy, new_theta).normalised()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f64::consts::PI;
+
+    use crate::GravLib::hardware::HeadingSource;
+    use crate::GravLib::sim::ManualClock;
+    use crate::GravLib::testing::{ScriptedEncoder, ScriptedHeading};
+
+    const STEP: Duration = Duration::from_millis(10);
+    /// One turn of the wheel is 10 inches.
+    const DIAMETER: f64 = 10.0 / PI;
+
+    fn ms(t: u64) -> Duration {
+        Duration::from_millis(t)
+    }
+
+    /// `Localisation` reading one centred vertical wheel and an IMU from
+    /// recorded traces.
+    fn replay(wheel: Vec<(Duration, f64)>, imu: Vec<(Duration, f64)>) -> (Localisation, ManualClock) {
+        let clock = ManualClock::new();
+        let wheel = TrackingWheel::new(ScriptedEncoder::new(wheel, clock.clone()), DIAMETER, 0.0, 1.0);
+        let imu: Arc<Mutex<dyn HeadingSource>> =
+            Arc::new(Mutex::new(ScriptedHeading::new(imu, clock.clone())));
+        let sensors = Sensors {
+            horizontal_wheels: Vec::new(),
+            vertical_wheels: vec![Arc::new(Mutex::new(wheel))],
+            imu,
+        };
+        let localisation = Localisation::with_clock(Arc::new(Mutex::new(sensors)), Arc::new(clock.clone()));
+        (localisation, clock)
+    }
+
+    fn run(localisation: &mut Localisation, clock: &ManualClock, steps: u32) {
+        for _ in 0..steps {
+            clock.advance(STEP);
+            localisation.update();
         }
     }
+
+    fn assert_pose(pose: Pose, x: f64, y: f64, theta: f64) {
+        let (px, py, ptheta) = pose.get_position();
+        assert!(
+            (px - x).abs() < 1e-9 && (py - y).abs() < 1e-9 && (ptheta - theta).abs() < 1e-9,
+            "expected ({x}, {y}, {theta}), got ({px}, {py}, {ptheta})"
+        );
+    }
+
+    #[test]
+    fn integrate_straight_follows_heading() {
+        // Facing +x, so forward travel is all x
+        let pose = Pose::from_inches(1.0, 2.0, 90.0);
+        assert_pose(integrate(&pose, 90.0, 5.0, 0.0, 0.0, 0.0), 6.0, 2.0, 90.0);
+        // Sliding right while facing +y
+        assert_pose(integrate(&Pose::default(), 0.0, 0.0, 3.0, 0.0, 0.0), 3.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn integrate_quarter_arc_lands_on_the_circle() {
+        // A quarter circle of radius 10 to the right ends at (10, 10)
+        let arc = 10.0 * PI / 2.0;
+        assert_pose(integrate(&Pose::default(), 90.0, arc, 0.0, 0.0, 0.0), 10.0, 10.0, 90.0);
+    }
+
+    #[test]
+    fn integrate_removes_turning_from_offset_wheels() {
+        // Turning 90° clockwise in place: a wheel 2" right of centre rolls
+        // backwards and one 3" ahead of centre slides right
+        let turn = PI / 2.0;
+        let pose = integrate(&Pose::default(), 90.0, -2.0 * turn, 3.0 * turn, 2.0, 3.0);
+        assert_pose(pose, 0.0, 0.0, 90.0);
+    }
+
+    #[test]
+    fn integrate_takes_the_short_way_across_the_seam() {
+        // 350° to 10° is a 20° turn, not 340°. Driving an arc across it
+        // should end up roughly straight ahead, not spun around.
+        let pose = Pose::from_inches(0.0, 0.0, 350.0);
+        let next = integrate(&pose, 10.0, 10.0, 0.0, 0.0, 0.0);
+        let (x, y, theta) = next.get_position();
+        assert!((theta - 10.0).abs() < 1e-9, "theta = {theta}");
+        assert!(x.abs() < 0.1 && (y - 10.0).abs() < 0.1, "ended at ({x}, {y})");
+    }
+
+    #[test]
+    fn straight_drive_moves_along_heading() {
+        // Two wheel turns over 100 ms, facing +y
+        let wheel = (0..=10).map(|i| (ms(i * 10), i as f64 * 72.0)).collect();
+        let (mut localisation, clock) = replay(wheel, vec![(ms(0), 0.0)]);
+
+        run(&mut localisation, &clock, 10);
+
+        let (x, y, theta) = localisation.pose().get_position();
+        assert!(x.abs() < 1e-9, "x = {x}");
+        assert!((y - 20.0).abs() < 1e-9, "y = {y}");
+        assert!(theta.abs() < 1e-9, "theta = {theta}");
+        assert_eq!(localisation.last_update(), Some(ms(100)));
+    }
+
+    #[test]
+    fn turn_in_place_keeps_position() {
+        let imu = (0..=10).map(|i| (ms(i * 10), i as f64 * 9.0)).collect();
+        let (mut localisation, clock) = replay(vec![(ms(0), 0.0)], imu);
+
+        run(&mut localisation, &clock, 10);
+
+        let (x, y, theta) = localisation.pose().get_position();
+        assert!(x.abs() < 1e-9 && y.abs() < 1e-9, "moved to ({x}, {y})");
+        assert!((theta - 90.0).abs() < 1e-9, "theta = {theta}");
+    }
+
+    #[test]
+    fn unreadable_sensors_are_skipped_until_they_read() {
+        // Neither the wheel nor the IMU reads for the first 50 ms. By then the
+        // wheel has turned once, which should still be counted.
+        let wheel = vec![(ms(50), 360.0), (ms(100), 720.0)];
+        let (mut localisation, clock) = replay(wheel, vec![(ms(50), 0.0)]);
+
+        run(&mut localisation, &clock, 4);
+        let (x, y, _) = localisation.pose().get_position();
+        assert_eq!((x, y), (0.0, 0.0));
+
+        run(&mut localisation, &clock, 6);
+        let (x, y, _) = localisation.pose().get_position();
+        assert!(x.abs() < 1e-9, "x = {x}");
+        assert!((y - 20.0).abs() < 1e-9, "y = {y}");
+    }
 }
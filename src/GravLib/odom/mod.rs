@@ -1,2 +1,6 @@
 pub mod sensors;
-pub mod localisation;
\ No newline at end of file
+pub mod localisation;
+pub mod pose;
+pub mod field;
+#[cfg(target_vendor = "vex")]
+pub mod task;
\ No newline at end of file
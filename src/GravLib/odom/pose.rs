@@ -0,0 +1,243 @@
+// pose.rs
+//
+// Field frame: +x to the right, +y away from the driver, heading measured
+// clockwise from +y (the same sense as the IMU). Robot frame: +x to the
+// robot's right, +y out of its front.
+use core::{fmt, ops::Mul};
+
+use uom::{
+    si::{
+        angle::{degree, radian},
+        f64::{Angle, Length},
+        length::{inch, meter},
+    },
+    ConstZero,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    x: Length,
+    y: Length,
+    theta: Angle,
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self::new(Length::ZERO, Length::ZERO, Angle::ZERO)
+    }
+}
+
+impl Pose {
+    pub fn new(x: Length, y: Length, theta: Angle) -> Self {
+        Self { x, y, theta }
+    }
+
+    /// Shorthand for the units the rest of the library works in.
+    pub fn from_inches(x: f64, y: f64, theta_degrees: f64) -> Self {
+        Self::new(
+            Length::new::<inch>(x),
+            Length::new::<inch>(y),
+            Angle::new::<degree>(theta_degrees),
+        )
+    }
+
+    pub fn x(&self) -> Length {
+        self.x
+    }
+
+    pub fn y(&self) -> Length {
+        self.y
+    }
+
+    pub fn theta(&self) -> Angle {
+        self.theta
+    }
+
+    /// Returns `(x, y, theta)` as inches, inches, degrees.
+    pub fn get_position(&self) -> (f64, f64, f64) {
+        (
+            self.x.get::<inch>(),
+            self.y.get::<inch>(),
+            self.theta.get::<degree>(),
+        )
+    }
+
+    /// The pose that undoes this one, so `pose * pose.inverse()` is the origin.
+    pub fn inverse(&self) -> Pose {
+        let theta = -self.theta;
+        let (x, y) = rotate_vector(-self.x, -self.y, theta);
+        Pose::new(x, y, normalise_angle(theta))
+    }
+
+    /// This pose expressed in the frame of `origin`.
+    pub fn relative_to(&self, origin: &Pose) -> Pose {
+        origin.inverse() * *self
+    }
+
+    /// Rotates a robot-frame vector into the field frame.
+    pub fn rotate_vector(&self, x: Length, y: Length) -> (Length, Length) {
+        rotate_vector(x, y, self.theta)
+    }
+
+    pub fn distance_to(&self, other: &Pose) -> Length {
+        let dx = (other.x - self.x).get::<meter>();
+        let dy = (other.y - self.y).get::<meter>();
+        Length::new::<meter>(libm::hypot(dx, dy))
+    }
+
+    /// Field heading that points from this pose towards `other`.
+    pub fn angle_to(&self, other: &Pose) -> Angle {
+        let dx = (other.x - self.x).get::<meter>();
+        let dy = (other.y - self.y).get::<meter>();
+        normalise_angle(Angle::new::<radian>(libm::atan2(dx, dy)))
+    }
+
+    /// Same pose with heading wrapped to [0°, 360°).
+    pub fn normalised(&self) -> Pose {
+        Pose::new(self.x, self.y, normalise_angle(self.theta))
+    }
+}
+
+impl Mul for Pose {
+    type Output = Pose;
+
+    /// Composes two poses: `rhs` is taken to be in the frame of `self`. The
+    /// heading is wrapped to [0°, 360°).
+    fn mul(self, rhs: Pose) -> Pose {
+        let (dx, dy) = self.rotate_vector(rhs.x, rhs.y);
+        Pose::new(self.x + dx, self.y + dy, normalise_angle(self.theta + rhs.theta))
+    }
+}
+
+impl fmt::Display for Pose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (x, y, theta) = self.get_position();
+        write!(f, "(x: {:+.2} in, y: {:+.2} in, θ: {:.2}°)", x, y, theta)
+    }
+}
+
+/// Rotates the vector `(x, y)` clockwise by `heading`.
+pub fn rotate_vector(x: Length, y: Length, heading: Angle) -> (Length, Length) {
+    let h = heading.get::<radian>();
+    let (sin_h, cos_h) = (libm::sin(h), libm::cos(h));
+    (x * cos_h + y * sin_h, y * cos_h - x * sin_h)
+}
+
+/// Wraps an angle to [-180°, 180°), i.e. the shortest turn.
+pub fn wrap_angle(angle: Angle) -> Angle {
+    Angle::new::<degree>(wrap_degrees(angle.get::<degree>()))
+}
+
+/// Wraps an angle to [0°, 360°).
+pub fn normalise_angle(angle: Angle) -> Angle {
+    Angle::new::<degree>(normalise_degrees(angle.get::<degree>()))
+}
+
+/// `wrap_angle` for a plain heading in degrees.
+pub fn wrap_degrees(angle: f64) -> f64 {
+    let a = angle + 180.0;
+    a - 360.0 * libm::floor(a / 360.0) - 180.0
+}
+
+/// `normalise_angle` for a plain heading in degrees.
+pub fn normalise_degrees(angle: f64) -> f64 {
+    angle - 360.0 * libm::floor(angle / 360.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn assert_pose(actual: Pose, x: f64, y: f64, theta: f64) {
+        let (ax, ay, at) = actual.get_position();
+        assert!(
+            (ax - x).abs() < EPSILON && (ay - y).abs() < EPSILON && wrap_degrees(at - theta).abs() < EPSILON,
+            "expected ({x}, {y}, {theta}), got {actual}"
+        );
+    }
+
+    fn poses() -> [Pose; 4] {
+        [
+            Pose::from_inches(0.0, 0.0, 0.0),
+            Pose::from_inches(12.0, -5.0, 90.0),
+            Pose::from_inches(-30.5, 48.0, 225.0),
+            Pose::from_inches(3.0, 7.0, -400.0),
+        ]
+    }
+
+    #[test]
+    fn rotate_vector_turns_clockwise() {
+        let one = Length::new::<inch>(1.0);
+        // Facing +x, the robot's front points along +x and its right along -y
+        let (x, y) = rotate_vector(Length::ZERO, one, Angle::new::<degree>(90.0));
+        assert!((x.get::<inch>() - 1.0).abs() < EPSILON && y.get::<inch>().abs() < EPSILON);
+        let (x, y) = rotate_vector(one, Length::ZERO, Angle::new::<degree>(90.0));
+        assert!(x.get::<inch>().abs() < EPSILON && (y.get::<inch>() + 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn mul_composes_in_the_frame_of_the_left_pose() {
+        // 10 in ahead of a robot facing +x, then turned a further 45°
+        let a = Pose::from_inches(5.0, 5.0, 90.0);
+        assert_pose(a * Pose::from_inches(0.0, 10.0, 45.0), 15.0, 5.0, 135.0);
+        // 2 in to the right of it
+        assert_pose(a * Pose::from_inches(2.0, 0.0, 0.0), 5.0, 3.0, 90.0);
+    }
+
+    #[test]
+    fn mul_wraps_the_heading() {
+        let turned = Pose::from_inches(0.0, 0.0, 300.0) * Pose::from_inches(0.0, 0.0, 120.0);
+        assert!((turned.theta().get::<degree>() - 60.0).abs() < EPSILON);
+        let back = Pose::from_inches(0.0, 0.0, 10.0) * Pose::from_inches(0.0, 0.0, -30.0);
+        assert!((back.theta().get::<degree>() - 340.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn pose_times_its_inverse_is_identity() {
+        for a in poses() {
+            assert_pose(a * a.inverse(), 0.0, 0.0, 0.0);
+            assert_pose(a.inverse() * a, 0.0, 0.0, 0.0);
+        }
+    }
+
+    #[test]
+    fn pose_relative_to_itself_is_identity() {
+        for a in poses() {
+            assert_pose(a.relative_to(&a), 0.0, 0.0, 0.0);
+        }
+    }
+
+    #[test]
+    fn relative_to_round_trips() {
+        for origin in poses() {
+            for pose in poses() {
+                let (x, y, theta) = pose.get_position();
+                assert_pose(origin * pose.relative_to(&origin), x, y, theta);
+            }
+        }
+    }
+
+    #[test]
+    fn wrap_helpers_pick_the_right_range() {
+        assert_eq!(wrap_degrees(190.0), -170.0);
+        assert_eq!(wrap_degrees(-180.0), -180.0);
+        assert_eq!(wrap_degrees(180.0), -180.0);
+        assert_eq!(wrap_degrees(725.0), 5.0);
+        assert_eq!(normalise_degrees(-90.0), 270.0);
+        assert_eq!(normalise_degrees(360.0), 0.0);
+        assert_eq!(normalise_degrees(725.0), 5.0);
+        assert!((wrap_angle(Angle::new::<degree>(350.0)).get::<degree>() + 10.0).abs() < EPSILON);
+        assert!((normalise_angle(Angle::new::<degree>(-10.0)).get::<degree>() - 350.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn angle_to_is_a_field_heading() {
+        let origin = Pose::default();
+        for (x, y, heading) in [(0.0, 1.0, 0.0), (1.0, 0.0, 90.0), (0.0, -1.0, 180.0), (-1.0, 0.0, 270.0)] {
+            let angle = origin.angle_to(&Pose::from_inches(x, y, 0.0)).get::<degree>();
+            assert!((angle - heading).abs() < EPSILON, "({x}, {y}): {angle}");
+        }
+    }
+}
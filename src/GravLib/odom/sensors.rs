@@ -1,41 +1,47 @@
-use vexide::{
-    devices::smart::RotationSensor,
-    devices::smart::InertialSensor,
-};
-
 use core::f64::consts::PI;
-use alloc::{sync::Arc, vec::Vec};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
 use spin::Mutex;
 
+use crate::GravLib::hardware::{Encoder, HeadingSource};
+
 pub struct Sensors {
     pub horizontal_wheels: Vec<Arc<Mutex<TrackingWheel>>>,
     pub vertical_wheels: Vec<Arc<Mutex<TrackingWheel>>>,
-    pub imu: Arc<Mutex<InertialSensor>>,
+    pub imu: Arc<Mutex<dyn HeadingSource>>,
 }
 
 pub struct TrackingWheel {
-    rotation: RotationSensor,
+    encoder: Box<dyn Encoder>,
     diameter: f64,
     offset: f64,
+    // Kept for geared tracking wheels; distances don't apply it yet
+    #[allow(dead_code)]
     ratio: f64,
 }
 
 impl TrackingWheel {
-    pub fn new(rotation: RotationSensor, diameter: f64, offset: f64, ratio: f64) -> Self {
+    /// `encoder` is any angle source, e.g. a vexide `RotationSensor` on the Brain
+    /// or a `ScriptedEncoder` when replaying a trace on a host.
+    ///
+    /// A vertical wheel should read positive driving forwards and a horizontal
+    /// wheel positive sliding right. `offset` is the distance from the tracking
+    /// centre, in the same units as `diameter`: positive to the right for a
+    /// vertical wheel and positive forwards for a horizontal one.
+    pub fn new(encoder: impl Encoder + 'static, diameter: f64, offset: f64, ratio: f64) -> Self {
         Self {
-            rotation,
+            encoder: Box::new(encoder),
             diameter,
             offset,
             ratio: if ratio == 0.0 { 1.0 } else { ratio },
         }
     }
 
-    /// Returns the distance travelled in the same units as `diameter`.
-    pub fn get_distance_travelled(&self) -> f64 {
-        // Assume get_angle() returns the angle in degrees.
-        let angle_degrees = self.rotation.position().unwrap().as_degrees();
+    /// Returns the distance travelled in the same units as `diameter`, or
+    /// `None` if the encoder couldn't be read, e.g. because it is unplugged.
+    pub fn get_distance_travelled(&self) -> Option<f64> {
+        let angle_degrees = self.encoder.angle()?;
 
-        angle_degrees * self.diameter * PI / 360.0
+        Some(angle_degrees * self.diameter * PI / 360.0)
     }
 
     /// Returns the offset of the tracking wheel.
@@ -44,8 +50,7 @@ impl TrackingWheel {
     }
 
     /// Resets the sensor and tracking wheel.
-    /// We assume that rotation.reset() resets the internal counter and returns an i64 status code.
     pub fn reset(&mut self) {
-        self.rotation.reset_position();
+        self.encoder.reset();
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,117 @@
+use core::{
+    sync::atomic::{AtomicU8, Ordering},
+    time::Duration,
+};
+
+use alloc::sync::Arc;
+use spin::Mutex;
+
+use vexide::devices::display::Display;
+use vexide::task::{self, Task};
+use vexide::time::{sleep_until, Instant};
+
+use crate::GravLib::misc::draw_pose;
+use crate::GravLib::odom::localisation::Localisation;
+
+const STOPPED: u8 = 0;
+const RUNNING: u8 = 1;
+const PAUSED: u8 = 2;
+
+/// Runs `Localisation::update` once per `period` on the vexide executor.
+pub struct OdometryTask {
+    localisation: Arc<Mutex<Localisation>>,
+    display: Option<Arc<Mutex<Display>>>,
+    period: Duration,
+    state: Arc<AtomicU8>,
+    task: Option<Task<()>>,
+}
+
+impl OdometryTask {
+    pub fn new(localisation: Arc<Mutex<Localisation>>, period: Duration) -> Self {
+        Self {
+            localisation,
+            display: None,
+            period,
+            state: Arc::new(AtomicU8::new(STOPPED)),
+            task: None,
+        }
+    }
+
+    /// Also draws the pose on `display` after every step.
+    pub fn with_display(mut self, display: Arc<Mutex<Display>>) -> Self {
+        self.display = Some(display);
+        self
+    }
+
+    /// Spawns the update loop, or resumes it if it is paused.
+    pub fn start(&mut self) {
+        if self.task.is_some() {
+            self.resume();
+            return;
+        }
+
+        self.state.store(RUNNING, Ordering::Release);
+
+        let localisation = Arc::clone(&self.localisation);
+        let display = self.display.clone();
+        let state = Arc::clone(&self.state);
+        let period = self.period;
+
+        self.task = Some(task::spawn(async move {
+            let mut next = Instant::now();
+            let mut was_paused = false;
+
+            loop {
+                match state.load(Ordering::Acquire) {
+                    RUNNING => {
+                        let mut local = localisation.lock();
+                        // Don't integrate whatever happened while paused
+                        if was_paused {
+                            local.resync();
+                            was_paused = false;
+                        }
+                        local.update();
+
+                        if let Some(display) = &display {
+                            draw_pose(&mut display.lock(), &local.m_pose.lock());
+                        }
+                    }
+                    PAUSED => was_paused = true,
+                    _ => break,
+                }
+
+                // Hold a fixed cadence, but don't try to catch up after a stall
+                next += period;
+                let now = Instant::now();
+                if next < now {
+                    next = now;
+                }
+                sleep_until(next).await;
+            }
+        }));
+    }
+
+    /// Freezes the pose. Wheel motion while paused is discarded on resume.
+    pub fn pause(&self) {
+        let _ = self.state.compare_exchange(RUNNING, PAUSED, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    pub fn resume(&self) {
+        let _ = self.state.compare_exchange(PAUSED, RUNNING, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    /// Ends the update loop. It can be started again with `start`.
+    pub fn stop(&mut self) {
+        self.state.store(STOPPED, Ordering::Release);
+        // Dropping the handle cancels the task at its next await point
+        self.task = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.load(Ordering::Acquire) == RUNNING
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.load(Ordering::Acquire) == PAUSED
+    }
+}
@@ -0,0 +1,63 @@
+extern crate alloc;
+
+pub mod parse;
+pub mod profile;
+pub mod spline;
+
+use alloc::vec::Vec;
+
+pub use parse::{parse_path, parse_path_bytes, PathParseError};
+pub use profile::ProfileConstraints;
+pub use spline::{CubicBezier, Curve, Hermite};
+
+/// One sample of a path, in field inches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Waypoint {
+    pub x: f64,
+    pub y: f64,
+    /// Target speed at this point, as a fraction of full power, 0.0 to 1.0.
+    pub speed: f64,
+    /// Signed curvature of the path here (1/inches), clockwise positive.
+    pub curvature: f64,
+}
+
+impl Waypoint {
+    pub fn new(x: f64, y: f64, speed: f64) -> Self {
+        Self { x, y, speed, curvature: 0.0 }
+    }
+
+    pub fn distance_to(&self, other: &Waypoint) -> f64 {
+        libm::hypot(other.x - self.x, other.y - self.y)
+    }
+}
+
+/// A densely sampled path for the robot to follow, in driving order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    pub points: Vec<Waypoint>,
+}
+
+impl Path {
+    pub fn new(points: Vec<Waypoint>) -> Self {
+        Self { points }
+    }
+
+    /// Samples `curves` end to end every `spacing` inches (at least
+    /// `spline::MIN_SPACING`) and assigns each point a curvature and a speed
+    /// within `constraints`.
+    pub fn generate(curves: &[&dyn Curve], spacing: f64, constraints: &ProfileConstraints) -> Self {
+        Self::new(profile::profile(&spline::sample(curves, spacing), constraints))
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&Waypoint> {
+        self.points.last()
+    }
+}
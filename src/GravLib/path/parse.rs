@@ -0,0 +1,217 @@
+// parse.rs
+//
+// Reads the plain `x,y,speed` point lists desktop path planners export, e.g.
+//
+//     let path = parse_path(include_str!("paths/skills.txt"), 127.0)?;
+//
+// Blank lines and `#` comments are skipped, as is an `x,y,speed` header
+// line before the first point. Reading stops at an `endData` line, after which planners like
+// path.jerryio keep their own metadata.
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::GravLib::path::{profile::curvature, Path, Waypoint};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathParseError {
+    /// `parse_path_bytes` was given bytes that aren't UTF-8.
+    InvalidUtf8,
+    /// The file has no points.
+    Empty,
+    /// A line doesn't have exactly three comma-separated fields.
+    WrongFieldCount { line: usize, found: usize },
+    /// A field isn't a finite number. `column` counts from 1.
+    InvalidNumber { line: usize, column: usize },
+    /// A speed is negative or above the maximum given to the parser.
+    SpeedOutOfRange { line: usize, speed: f64 },
+}
+
+impl fmt::Display for PathParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathParseError::InvalidUtf8 => write!(f, "path file is not valid UTF-8"),
+            PathParseError::Empty => write!(f, "path file has no points"),
+            PathParseError::WrongFieldCount { line, found } => {
+                write!(f, "line {}: expected 3 fields (x,y,speed), found {}", line, found)
+            }
+            PathParseError::InvalidNumber { line, column } => {
+                write!(f, "line {}: field {} is not a number", line, column)
+            }
+            PathParseError::SpeedOutOfRange { line, speed } => {
+                write!(f, "line {}: speed {} is out of range", line, speed)
+            }
+        }
+    }
+}
+
+const HEADER: [&str; 3] = ["x", "y", "speed"];
+
+/// Whether `fields` are the `x,y,speed` column names, in any case.
+fn is_header(fields: &[&str]) -> bool {
+    fields.len() == HEADER.len() && fields.iter().zip(HEADER).all(|(f, h)| f.eq_ignore_ascii_case(h))
+}
+
+/// Parses a path file. Speeds in the file run from 0 to `max_speed` (e.g.
+/// 127.0 for files made for LemLib, or 1.0 if they are already fractions)
+/// and are scaled to fractions of full power.
+pub fn parse_path(text: &str, max_speed: f64) -> Result<Path, PathParseError> {
+    let mut points = Vec::new();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw.split('#').next().unwrap_or("").trim();
+
+        if line.is_empty() {
+            continue;
+        }
+        if line == "endData" {
+            break;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+
+        // Column header, only allowed before any points
+        if points.is_empty() && is_header(&fields) {
+            continue;
+        }
+        if fields.len() != 3 {
+            return Err(PathParseError::WrongFieldCount { line: line_number, found: fields.len() });
+        }
+
+        let mut values = [0.0; 3];
+        for (column, field) in fields.iter().enumerate() {
+            values[column] = field
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or(PathParseError::InvalidNumber { line: line_number, column: column + 1 })?;
+        }
+
+        let [x, y, speed] = values;
+        if speed < 0.0 || speed > max_speed {
+            return Err(PathParseError::SpeedOutOfRange { line: line_number, speed });
+        }
+
+        points.push(Waypoint::new(x, y, speed / max_speed));
+    }
+
+    if points.is_empty() {
+        return Err(PathParseError::Empty);
+    }
+
+    // Files don't carry curvature, so work it out from the neighbours
+    for i in 1..points.len().saturating_sub(1) {
+        let (a, b, c) = (points[i - 1], points[i], points[i + 1]);
+        points[i].curvature = curvature((a.x, a.y), (b.x, b.y), (c.x, c.y));
+    }
+
+    Ok(Path::new(points))
+}
+
+/// Same as `parse_path`, for files embedded with `include_bytes!`.
+pub fn parse_path_bytes(bytes: &[u8], max_speed: f64) -> Result<Path, PathParseError> {
+    let text = core::str::from_utf8(bytes).map_err(|_| PathParseError::InvalidUtf8)?;
+    parse_path(text, max_speed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_planner_export() {
+        let text = "\
+x,y,speed
+# start of the skills run
+0, 0, 127
+0, 10, 127   # full speed
+10,20,63.5
+
+-10.5,30,0
+endData
+#PATH.JERRYIO-DATA {\"appVersion\": \"0.8.0\"}
+";
+        let path = parse_path(text, 127.0).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(path.points[0], Waypoint::new(0.0, 0.0, 1.0));
+        assert_eq!((path.points[2].x, path.points[2].y, path.points[2].speed), (10.0, 20.0, 0.5));
+        assert_eq!(path.points[3].speed, 0.0);
+
+        // Curvature from the neighbours, none at the ends
+        assert_eq!(path.points[0].curvature, 0.0);
+        assert!(path.points[1].curvature > 0.0);
+        assert!(path.points[2].curvature < 0.0);
+        assert_eq!(path.points[3].curvature, 0.0);
+
+        assert_eq!(parse_path_bytes(text.as_bytes(), 127.0), Ok(path));
+    }
+
+    #[test]
+    fn header_is_only_skipped_before_the_points() {
+        assert_eq!(parse_path("X, Y, Speed\n1,2,1", 1.0).unwrap().len(), 1);
+        assert_eq!(
+            parse_path("1,2,1\nx,y,speed", 1.0),
+            Err(PathParseError::InvalidNumber { line: 2, column: 1 })
+        );
+    }
+
+    #[test]
+    fn first_line_starting_with_a_letter_is_not_a_header() {
+        assert_eq!(
+            parse_path("inf,1,2\n1,2,1", 127.0),
+            Err(PathParseError::InvalidNumber { line: 1, column: 1 })
+        );
+        assert_eq!(
+            parse_path("x,y\n1,2,1", 127.0),
+            Err(PathParseError::WrongFieldCount { line: 1, found: 2 })
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        assert_eq!(parse_path_bytes(&[b'1', 0xff, b'\n'], 1.0), Err(PathParseError::InvalidUtf8));
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(parse_path("", 1.0), Err(PathParseError::Empty));
+        assert_eq!(parse_path("x,y,speed\n# nothing\n\nendData\n1,2,1", 1.0), Err(PathParseError::Empty));
+    }
+
+    #[test]
+    fn wrong_field_count() {
+        assert_eq!(
+            parse_path("1,2,1\n1,2\n", 1.0),
+            Err(PathParseError::WrongFieldCount { line: 2, found: 2 })
+        );
+        assert_eq!(
+            parse_path("1,2,1,0", 1.0),
+            Err(PathParseError::WrongFieldCount { line: 1, found: 4 })
+        );
+    }
+
+    #[test]
+    fn invalid_number() {
+        assert_eq!(
+            parse_path("1,2,1\n1,two,1", 1.0),
+            Err(PathParseError::InvalidNumber { line: 2, column: 2 })
+        );
+        assert_eq!(
+            parse_path("1,2,NaN", 1.0),
+            Err(PathParseError::InvalidNumber { line: 1, column: 3 })
+        );
+    }
+
+    #[test]
+    fn speed_out_of_range() {
+        assert_eq!(
+            parse_path("1,2,128", 127.0),
+            Err(PathParseError::SpeedOutOfRange { line: 1, speed: 128.0 })
+        );
+        assert_eq!(
+            parse_path("1,2,1\n1,2,-0.5", 1.0),
+            Err(PathParseError::SpeedOutOfRange { line: 2, speed: -0.5 })
+        );
+    }
+}
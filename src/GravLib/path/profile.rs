@@ -0,0 +1,211 @@
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use crate::GravLib::path::Waypoint;
+
+/// Limits a velocity profile has to respect. Velocities are in inches per
+/// second and accelerations in inches per second squared.
+#[derive(Clone, Copy, Debug)]
+pub struct ProfileConstraints {
+    /// Top speed of the robot. Waypoint speeds are given as a fraction of it.
+    pub max_velocity: f64,
+    /// Fastest the robot may speed up or slow down along the path.
+    pub max_acceleration: f64,
+    /// Fastest the robot may accelerate sideways in a curve before it slides.
+    pub max_lateral_acceleration: f64,
+    /// Speed at the first point. Keep this above zero, or a follower that
+    /// reads the speed at the nearest point will never get going.
+    pub start_velocity: f64,
+    /// Speed at the last point.
+    pub end_velocity: f64,
+}
+
+impl Default for ProfileConstraints {
+    fn default() -> Self {
+        Self {
+            max_velocity: 60.0,
+            max_acceleration: 80.0,
+            max_lateral_acceleration: 60.0,
+            start_velocity: 10.0,
+            end_velocity: 0.0,
+        }
+    }
+}
+
+/// Signed curvature (1/inches) of the circle through three points.
+/// Positive curves clockwise, matching headings.
+pub fn curvature(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    let cross = (b.0 - a.0) * (c.1 - b.1) - (b.1 - a.1) * (c.0 - b.0);
+    let sides = libm::hypot(b.0 - a.0, b.1 - a.1)
+        * libm::hypot(c.0 - b.0, c.1 - b.1)
+        * libm::hypot(c.0 - a.0, c.1 - a.1);
+
+    if sides == 0.0 { 0.0 } else { -2.0 * cross / sides }
+}
+
+/// Turns evenly spaced points into waypoints with curvature and speed.
+///
+/// Each point first gets the fastest speed it can take its curve at, then a
+/// backward pass makes sure the robot can brake in time for every slow point
+/// ahead, and a forward pass makes sure it can accelerate to reach it.
+pub fn profile(points: &[(f64, f64)], constraints: &ProfileConstraints) -> Vec<Waypoint> {
+    let n = points.len();
+    let dist = |i: usize, j: usize| libm::hypot(points[j].0 - points[i].0, points[j].1 - points[i].1);
+
+    let curvatures: Vec<f64> = (0..n)
+        .map(|i| {
+            if i == 0 || i == n - 1 {
+                0.0
+            } else {
+                curvature(points[i - 1], points[i], points[i + 1])
+            }
+        })
+        .collect();
+
+    // 1. Cornering limit: v² * κ must stay under the lateral acceleration
+    let mut velocities: Vec<f64> = curvatures
+        .iter()
+        .map(|k| {
+            if *k == 0.0 {
+                constraints.max_velocity
+            } else {
+                libm::sqrt(constraints.max_lateral_acceleration / k.abs()).min(constraints.max_velocity)
+            }
+        })
+        .collect();
+
+    if n > 0 {
+        velocities[0] = velocities[0].min(constraints.start_velocity);
+        velocities[n - 1] = velocities[n - 1].min(constraints.end_velocity);
+    }
+
+    // 2. Backward pass: leave room to brake
+    for i in (0..n.saturating_sub(1)).rev() {
+        let reachable = libm::sqrt(
+            velocities[i + 1] * velocities[i + 1] + 2.0 * constraints.max_acceleration * dist(i, i + 1),
+        );
+        velocities[i] = velocities[i].min(reachable);
+    }
+
+    // 3. Forward pass: leave room to accelerate
+    for i in 1..n {
+        let reachable = libm::sqrt(
+            velocities[i - 1] * velocities[i - 1] + 2.0 * constraints.max_acceleration * dist(i - 1, i),
+        );
+        velocities[i] = velocities[i].min(reachable);
+    }
+
+    points
+        .iter()
+        .zip(curvatures)
+        .zip(velocities)
+        .map(|((p, k), v)| Waypoint {
+            x: p.0,
+            y: p.1,
+            speed: v / constraints.max_velocity,
+            curvature: k,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Points every inch along a straight line up the +y axis.
+    fn straight(length: usize) -> Vec<(f64, f64)> {
+        (0..=length).map(|y| (0.0, y as f64)).collect()
+    }
+
+    /// Points every inch around a circle of `radius` about the origin,
+    /// clockwise from the top.
+    fn clockwise_arc(radius: f64, length: usize) -> Vec<(f64, f64)> {
+        (0..=length)
+            .map(|i| {
+                let a = i as f64 / radius;
+                (radius * libm::sin(a), radius * libm::cos(a))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn curvature_is_positive_clockwise() {
+        // Heading up +y, then bearing right
+        assert!(curvature((0.0, 0.0), (0.0, 1.0), (1.0, 2.0)) > 0.0);
+        // Bearing left
+        assert!(curvature((0.0, 0.0), (0.0, 1.0), (-1.0, 2.0)) < 0.0);
+        assert_eq!(curvature((0.0, 0.0), (0.0, 1.0), (0.0, 2.0)), 0.0);
+        assert_eq!(curvature((0.0, 0.0), (0.0, 0.0), (0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn curvature_of_a_circle_is_one_over_its_radius() {
+        let arc = clockwise_arc(10.0, 3);
+        let k = curvature(arc[0], arc[1], arc[2]);
+        assert!((k - 0.1).abs() < 1e-3, "{k}");
+    }
+
+    #[test]
+    fn curves_are_limited_by_lateral_acceleration() {
+        let constraints = ProfileConstraints {
+            max_acceleration: 1e6,
+            start_velocity: 60.0,
+            end_velocity: 60.0,
+            ..Default::default()
+        };
+        let waypoints = profile(&clockwise_arc(20.0, 30), &constraints);
+
+        // v² / r = 60 in/s² on a 20" radius
+        let limit = libm::sqrt(60.0 * 20.0) / 60.0;
+        for w in &waypoints[1..waypoints.len() - 1] {
+            assert!((w.curvature - 0.05).abs() < 1e-3, "{}", w.curvature);
+            assert!((w.speed - limit).abs() < 1e-3, "{}", w.speed);
+        }
+
+        // A straight stays at top speed
+        let waypoints = profile(&straight(30), &constraints);
+        assert!(waypoints.iter().all(|w| w.speed == 1.0 && w.curvature == 0.0));
+    }
+
+    #[test]
+    fn speeds_change_no_faster_than_max_acceleration() {
+        let constraints = ProfileConstraints::default();
+        let mut points = straight(60);
+        // A tight bend that the robot must brake for and speed up out of
+        points.extend(clockwise_arc(5.0, 10).iter().skip(1).map(|(x, y)| (*x, y + 55.0)));
+        let (ex, ey) = points[points.len() - 1];
+        points.extend((1..=60).map(|i| (ex + i as f64, ey)));
+        let waypoints = profile(&points, &constraints);
+        assert!(waypoints[65].speed < 0.5 && waypoints[30].speed == 1.0);
+
+        for pair in waypoints.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let (va, vb) = (a.speed * constraints.max_velocity, b.speed * constraints.max_velocity);
+            let d = a.distance_to(&b);
+            assert!(
+                (vb * vb - va * va).abs() <= 2.0 * constraints.max_acceleration * d + 1e-6,
+                "{va} -> {vb} over {d}"
+            );
+        }
+    }
+
+    #[test]
+    fn starts_at_start_velocity_and_ends_stopped() {
+        let constraints = ProfileConstraints::default();
+        let waypoints = profile(&straight(60), &constraints);
+
+        assert!((waypoints[0].speed - 10.0 / 60.0).abs() < 1e-12);
+        assert_eq!(waypoints[60].speed, 0.0);
+        // Braking to a stop over the last stretch, not all at once
+        assert!(waypoints[59].speed > 0.0);
+        assert!(waypoints[50..].windows(2).all(|p| p[1].speed < p[0].speed));
+    }
+
+    #[test]
+    fn empty_and_single_point_paths() {
+        assert!(profile(&[], &ProfileConstraints::default()).is_empty());
+        let waypoints = profile(&[(1.0, 2.0)], &ProfileConstraints::default());
+        assert_eq!(waypoints, [Waypoint { x: 1.0, y: 2.0, speed: 0.0, curvature: 0.0 }]);
+    }
+}
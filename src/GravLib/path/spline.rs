@@ -0,0 +1,159 @@
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use uom::si::angle::radian;
+
+use crate::GravLib::odom::pose::Pose;
+
+/// Fine steps taken along each curve before resampling it evenly.
+const FINE_STEPS: usize = 400;
+
+/// Closest spacing (inches) `sample` will space points. Anything tighter,
+/// including zero or negative, is raised to this.
+pub const MIN_SPACING: f64 = 0.1;
+
+/// A parametric curve from `t = 0` to `t = 1`, in field inches.
+pub trait Curve {
+    fn point(&self, t: f64) -> (f64, f64);
+}
+
+/// A cubic Bézier curve: starts at `p0` heading towards `p1`, and ends at
+/// `p3` arriving from the direction of `p2`.
+#[derive(Clone, Copy, Debug)]
+pub struct CubicBezier {
+    pub p0: (f64, f64),
+    pub p1: (f64, f64),
+    pub p2: (f64, f64),
+    pub p3: (f64, f64),
+}
+
+impl CubicBezier {
+    pub fn new(p0: (f64, f64), p1: (f64, f64), p2: (f64, f64), p3: (f64, f64)) -> Self {
+        Self { p0, p1, p2, p3 }
+    }
+}
+
+impl Curve for CubicBezier {
+    fn point(&self, t: f64) -> (f64, f64) {
+        let u = 1.0 - t;
+        let (b0, b1, b2, b3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        (
+            b0 * self.p0.0 + b1 * self.p1.0 + b2 * self.p2.0 + b3 * self.p3.0,
+            b0 * self.p0.1 + b1 * self.p1.1 + b2 * self.p2.1 + b3 * self.p3.1,
+        )
+    }
+}
+
+/// A cubic Hermite curve between two points with given tangent vectors.
+#[derive(Clone, Copy, Debug)]
+pub struct Hermite {
+    pub start: (f64, f64),
+    pub start_tangent: (f64, f64),
+    pub end: (f64, f64),
+    pub end_tangent: (f64, f64),
+}
+
+impl Hermite {
+    pub fn new(
+        start: (f64, f64),
+        start_tangent: (f64, f64),
+        end: (f64, f64),
+        end_tangent: (f64, f64),
+    ) -> Self {
+        Self { start, start_tangent, end, end_tangent }
+    }
+
+    /// A curve that leaves `start` along its heading and arrives at `end`
+    /// along its heading. `scale` sets the tangent length in inches; around
+    /// the straight-line distance between the poses is a good start.
+    pub fn from_poses(start: &Pose, end: &Pose, scale: f64) -> Self {
+        let tangent = |pose: &Pose| {
+            let theta = pose.theta().get::<radian>();
+            (scale * libm::sin(theta), scale * libm::cos(theta))
+        };
+        let (sx, sy, _) = start.get_position();
+        let (ex, ey, _) = end.get_position();
+        Self::new((sx, sy), tangent(start), (ex, ey), tangent(end))
+    }
+}
+
+impl Curve for Hermite {
+    fn point(&self, t: f64) -> (f64, f64) {
+        let (t2, t3) = (t * t, t * t * t);
+        let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+        let h10 = t3 - 2.0 * t2 + t;
+        let h01 = -2.0 * t3 + 3.0 * t2;
+        let h11 = t3 - t2;
+        (
+            h00 * self.start.0 + h10 * self.start_tangent.0 + h01 * self.end.0 + h11 * self.end_tangent.0,
+            h00 * self.start.1 + h10 * self.start_tangent.1 + h01 * self.end.1 + h11 * self.end_tangent.1,
+        )
+    }
+}
+
+/// Walks the curves end to end and returns points `spacing` inches apart
+/// along them, but no closer than `MIN_SPACING`. The last point always lands
+/// on the end of the last curve.
+pub fn sample(curves: &[&dyn Curve], spacing: f64) -> Vec<(f64, f64)> {
+    let spacing = spacing.max(MIN_SPACING);
+    let mut out = Vec::new();
+    let mut prev: Option<(f64, f64)> = None;
+    // Distance travelled since the last emitted point
+    let mut carried = 0.0;
+
+    for curve in curves {
+        for i in 0..=FINE_STEPS {
+            let p = curve.point(i as f64 / FINE_STEPS as f64);
+            let Some(mut from) = prev else {
+                out.push(p);
+                prev = Some(p);
+                continue;
+            };
+
+            let mut remaining = libm::hypot(p.0 - from.0, p.1 - from.1);
+            while carried + remaining >= spacing {
+                let t = (spacing - carried) / remaining;
+                from = (from.0 + (p.0 - from.0) * t, from.1 + (p.1 - from.1) * t);
+                out.push(from);
+                remaining -= spacing - carried;
+                carried = 0.0;
+            }
+            carried += remaining;
+            prev = Some(p);
+        }
+    }
+
+    if let Some(end) = prev {
+        if carried > 1e-6 {
+            out.push(end);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A straight line from the origin to (0, 10).
+    fn line() -> CubicBezier {
+        CubicBezier::new((0.0, 0.0), (0.0, 10.0 / 3.0), (0.0, 20.0 / 3.0), (0.0, 10.0))
+    }
+
+    #[test]
+    fn points_are_evenly_spaced_and_end_on_the_curve() {
+        let points = sample(&[&line()], 2.5);
+        assert_eq!(points.len(), 5);
+        for (i, (x, y)) in points.iter().enumerate() {
+            assert!(x.abs() < 1e-9 && (y - 2.5 * i as f64).abs() < 1e-6, "point {i} at ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn spacing_below_the_minimum_is_raised() {
+        for spacing in [0.0, -1.0, f64::NAN] {
+            assert_eq!(sample(&[&line()], spacing).len(), 101);
+        }
+    }
+}
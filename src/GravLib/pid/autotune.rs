@@ -0,0 +1,303 @@
+// autotune.rs
+//
+// Relay (Åström–Hägglund) auto-tuning. Instead of a PID, a relay drives the
+// plant at +amplitude or -amplitude depending on the sign of the error, which
+// makes it oscillate steadily about the target. From the size and period of
+// that oscillation we get the ultimate gain Ku and period Tu, and from those
+// the usual tuning rules give a starting set of gains.
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{f64::consts::PI, time::Duration};
+
+#[cfg(target_vendor = "vex")]
+use alloc::sync::Arc;
+#[cfg(target_vendor = "vex")]
+use spin::Mutex;
+#[cfg(target_vendor = "vex")]
+use uom::si::angle::degree;
+
+#[cfg(target_vendor = "vex")]
+use crate::GravLib::actuator::MotorGroup;
+#[cfg(target_vendor = "vex")]
+use crate::GravLib::motions::{
+    chassis::{Chassis, MOTION_PERIOD},
+    motion_cancel_helper::MotionCancelHelper,
+};
+#[cfg(target_vendor = "vex")]
+use crate::GravLib::odom::pose::wrap_degrees;
+use crate::GravLib::pid::Gains;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RelayConfig {
+    /// Relay output, in the plant's output units: a fraction of full power
+    /// for `autotune_turn`, volts for `autotune_motor_group`.
+    pub amplitude: f64,
+    /// The relay only switches once the error is this far past zero, so
+    /// sensor noise can't make it chatter. In the plant's error units.
+    pub hysteresis: f64,
+    /// Oscillations to average over, at least 1. The first one is always
+    /// discarded on top of these.
+    pub cycles: usize,
+    pub timeout: Duration,
+}
+
+impl RelayConfig {
+    pub fn new(amplitude: f64) -> Self {
+        Self {
+            amplitude,
+            hysteresis: 0.0,
+            cycles: 4,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Ultimate gain and period measured by a relay test.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RelayResult {
+    /// Output units per error unit.
+    pub ultimate_gain: f64,
+    pub ultimate_period: Duration,
+}
+
+impl RelayResult {
+    /// Classic Ziegler–Nichols PID gains. Fast, but with a fair amount of
+    /// overshoot.
+    pub fn ziegler_nichols(&self) -> Gains {
+        let ku = self.ultimate_gain;
+        let tu = self.ultimate_period.as_secs_f64();
+        Gains::new((0.6 * ku) as f32, (1.2 * ku / tu) as f32, (0.075 * ku * tu) as f32)
+    }
+
+    /// Tyreus–Luyben PID gains. More conservative than Ziegler–Nichols, and
+    /// usually the better starting point for a drivetrain.
+    pub fn tyreus_luyben(&self) -> Gains {
+        let ku = self.ultimate_gain;
+        let tu = self.ultimate_period.as_secs_f64();
+        let kp = ku / 2.2;
+        Gains::new(kp as f32, (kp / (2.2 * tu)) as f32, (kp * tu / 6.3) as f32)
+    }
+}
+
+/// One full oscillation of the error.
+struct Cycle {
+    period: Duration,
+    amplitude: f64,
+}
+
+/// The relay experiment itself, with no hardware or timing of its own. Feed
+/// it the error every iteration; it returns the output to apply, or `None`
+/// once enough oscillations have been measured.
+pub struct RelayTest {
+    config: RelayConfig,
+    high: Option<bool>,
+    elapsed: Duration,
+    last_rise: Option<Duration>,
+    max_error: f64,
+    min_error: f64,
+    cycles: Vec<Cycle>,
+}
+
+impl RelayTest {
+    /// A `config` asking for no cycles measures one instead.
+    pub fn new(mut config: RelayConfig) -> Self {
+        config.cycles = config.cycles.max(1);
+        Self {
+            config,
+            high: None,
+            elapsed: Duration::ZERO,
+            last_rise: None,
+            max_error: f64::NEG_INFINITY,
+            min_error: f64::INFINITY,
+            cycles: Vec::new(),
+        }
+    }
+
+    /// `error` is target minus measurement and `dt` the time since the
+    /// previous call.
+    pub fn step(&mut self, error: f64, dt: Duration) -> Option<f64> {
+        if self.is_done() {
+            return None;
+        }
+        self.elapsed += dt;
+        self.max_error = self.max_error.max(error);
+        self.min_error = self.min_error.min(error);
+
+        let hysteresis = self.config.hysteresis;
+        let high = match self.high {
+            None => error >= 0.0,
+            Some(true) => error > -hysteresis,
+            Some(false) => error > hysteresis,
+        };
+
+        // A cycle runs from one switch to high to the next
+        if self.high == Some(false) && high {
+            if let Some(rise) = self.last_rise {
+                self.cycles.push(Cycle {
+                    period: self.elapsed - rise,
+                    amplitude: (self.max_error - self.min_error) / 2.0,
+                });
+            }
+            self.last_rise = Some(self.elapsed);
+            self.max_error = error;
+            self.min_error = error;
+        }
+        self.high = Some(high);
+
+        if self.is_done() {
+            return None;
+        }
+        Some(if high { self.config.amplitude } else { -self.config.amplitude })
+    }
+
+    fn is_done(&self) -> bool {
+        self.cycles.len() > self.config.cycles
+    }
+
+    /// The measured ultimate gain and period, once the test has finished.
+    pub fn result(&self) -> Option<RelayResult> {
+        if !self.is_done() {
+            return None;
+        }
+
+        // 1. Average over every cycle except the first, which still carries
+        // the transient from the start
+        let measured = &self.cycles[1..];
+        if measured.is_empty() {
+            return None;
+        }
+        let n = measured.len() as f64;
+        let amplitude = measured.iter().map(|c| c.amplitude).sum::<f64>() / n;
+        let period = measured.iter().map(|c| c.period).sum::<Duration>() / measured.len() as u32;
+
+        // 2. Describing function of a relay with hysteresis
+        let eps = self.config.hysteresis;
+        let effective = libm::sqrt((amplitude * amplitude - eps * eps).max(0.0));
+        if effective <= 0.0 {
+            return None;
+        }
+
+        Some(RelayResult {
+            ultimate_gain: 4.0 * self.config.amplitude / (PI * effective),
+            ultimate_period: period,
+        })
+    }
+}
+
+/// Runs a relay test by turning in place about `target`, a field heading in
+/// degrees. The result is in fraction of full power per degree, the units the
+/// turn PID works in. Returns `None` if the test timed out or was cancelled
+/// before it finished.
+#[cfg(target_vendor = "vex")]
+pub async fn autotune_turn(chassis: &Chassis, target: f64, config: RelayConfig) -> Option<RelayResult> {
+    let mut test = RelayTest::new(config);
+    chassis
+        .run(config.timeout, false, |pose, dt| {
+            let error = wrap_degrees(target - pose.theta().get::<degree>());
+            test.step(error, dt).map(|power| (power, -power))
+        })
+        .await;
+    test.result()
+}
+
+/// Runs a relay test on a single `MotorGroup`, holding it about `offset`
+/// degrees from where it starts. The result is in volts per degree. Returns
+/// `None` if the test timed out or was cancelled before it finished.
+#[cfg(target_vendor = "vex")]
+pub async fn autotune_motor_group(
+    group: &Arc<Mutex<MotorGroup>>,
+    offset: f64,
+    config: RelayConfig,
+) -> Option<RelayResult> {
+    let mut test = RelayTest::new(config);
+    let target = group.lock().position().ok()? + offset;
+
+    // A motor failing part way would spoil the measurement, so give up
+    let mut helper = MotionCancelHelper::new(MOTION_PERIOD).with_timeout(config.timeout);
+    while helper.wait().await {
+        let Ok(position) = group.lock().position() else {
+            break;
+        };
+        let Some(volts) = test.step(target - position, helper.dt()) else {
+            break;
+        };
+        if group.lock().move_voltage(volts).is_err() {
+            break;
+        }
+    }
+
+    let _ = group.lock().move_voltage(0.0);
+    test.result()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{boxed::Box, vec};
+
+    use crate::GravLib::actuator::MotorGroup;
+    use crate::GravLib::hardware::{Actuator, HeadingSource};
+    use crate::GravLib::odom::pose::wrap_degrees;
+    use crate::GravLib::sim::{Side, SimConfig, Simulation};
+
+    const TICK: Duration = Duration::from_millis(10);
+
+    fn group(sim: &Simulation, side: Side) -> MotorGroup {
+        MotorGroup::from_actuators(vec![
+            Box::new(sim.motor(side)) as Box<dyn Actuator>,
+            Box::new(sim.motor(side)),
+        ])
+    }
+
+    #[test]
+    fn relay_turning_in_place_finds_ultimate_gain() {
+        let mut sim = Simulation::new(SimConfig::default(), TICK);
+        let mut left = group(&sim, Side::Left);
+        let mut right = group(&sim, Side::Right);
+        let imu = sim.imu();
+
+        // Same loop as `autotune_turn`, about the starting heading
+        let mut test = RelayTest::new(RelayConfig {
+            hysteresis: 1.0,
+            ..RelayConfig::new(0.4)
+        });
+        for _ in 0..1000 {
+            let error = wrap_degrees(0.0 - imu.heading().unwrap());
+            let Some(power) = test.step(error, TICK) else {
+                break;
+            };
+            left.move_voltage(power * 12.0).unwrap();
+            right.move_voltage(-power * 12.0).unwrap();
+            sim.tick();
+        }
+
+        let result = test.result().expect("relay test didn't finish");
+        assert!(result.ultimate_gain > 0.0 && result.ultimate_gain.is_finite());
+        assert!(
+            result.ultimate_period > TICK * 2 && result.ultimate_period < Duration::from_secs(2),
+            "period {:?}",
+            result.ultimate_period
+        );
+        let gains = result.tyreus_luyben();
+        assert!(gains.kP > 0.0 && gains.kI > 0.0 && gains.kD > 0.0);
+    }
+
+    #[test]
+    fn zero_cycles_measures_one() {
+        let mut test = RelayTest::new(RelayConfig {
+            cycles: 0,
+            ..RelayConfig::new(1.0)
+        });
+
+        // A square wave error, 4 ticks each side of zero
+        let mut steps = 0;
+        while test.step(if steps % 8 < 4 { 2.0 } else { -2.0 }, TICK).is_some() {
+            steps += 1;
+            assert!(steps < 100, "never finished");
+        }
+
+        let result = test.result().unwrap();
+        assert_eq!(result.ultimate_period, TICK * 8);
+    }
+}
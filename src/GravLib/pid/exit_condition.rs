@@ -0,0 +1,255 @@
+// exit_condition.rs
+//
+// "Am I done yet" checks for a control loop. Each one is fed the error every
+// iteration and says whether the loop should stop; they combine with `or` and
+// `and` so turns, drives and swings can all settle the same way.
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
+
+#[derive(Clone, Debug)]
+pub enum ExitCondition {
+    /// The error has stayed within `range` for `time`.
+    Range {
+        range: f64,
+        time: Duration,
+        timer: Duration,
+    },
+    /// The error has changed by less than `threshold` per second for `time`,
+    /// i.e. the robot has stopped making progress.
+    Velocity {
+        threshold: f64,
+        time: Duration,
+        timer: Duration,
+        previous: Option<f64>,
+    },
+    /// `time` has passed since the first update.
+    Timeout { time: Duration, timer: Duration },
+    /// Any of the conditions is met.
+    Any(Vec<ExitCondition>),
+    /// All of the conditions are met at once.
+    All(Vec<ExitCondition>),
+}
+
+impl ExitCondition {
+    pub fn range(range: f64, time: Duration) -> Self {
+        ExitCondition::Range {
+            range,
+            time,
+            timer: Duration::ZERO,
+        }
+    }
+
+    pub fn velocity(threshold: f64, time: Duration) -> Self {
+        ExitCondition::Velocity {
+            threshold,
+            time,
+            timer: Duration::ZERO,
+            previous: None,
+        }
+    }
+
+    pub fn timeout(time: Duration) -> Self {
+        ExitCondition::Timeout {
+            time,
+            timer: Duration::ZERO,
+        }
+    }
+
+    /// LemLib's pair of exits: stop once within `small_range` for
+    /// `small_time`, or, if the robot can't get that close, once within
+    /// `large_range` for the longer `large_time`.
+    pub fn small_large(small_range: f64, small_time: Duration, large_range: f64, large_time: Duration) -> Self {
+        ExitCondition::Any(vec![
+            Self::range(small_range, small_time),
+            Self::range(large_range, large_time),
+        ])
+    }
+
+    /// Met when either this or `other` is.
+    pub fn or(self, other: ExitCondition) -> Self {
+        match self {
+            ExitCondition::Any(mut conditions) => {
+                conditions.push(other);
+                ExitCondition::Any(conditions)
+            }
+            condition => ExitCondition::Any(vec![condition, other]),
+        }
+    }
+
+    /// Met only when this and `other` both are.
+    pub fn and(self, other: ExitCondition) -> Self {
+        match self {
+            ExitCondition::All(mut conditions) => {
+                conditions.push(other);
+                ExitCondition::All(conditions)
+            }
+            condition => ExitCondition::All(vec![condition, other]),
+        }
+    }
+
+    /// Feeds in the latest `error` and the time `dt` since the previous
+    /// update. Returns `true` once the condition is met.
+    pub fn update(&mut self, error: f64, dt: Duration) -> bool {
+        match self {
+            ExitCondition::Range { range, time, timer } => {
+                if error.abs() < *range {
+                    *timer += dt;
+                } else {
+                    *timer = Duration::ZERO;
+                }
+                *timer >= *time
+            }
+            ExitCondition::Velocity {
+                threshold,
+                time,
+                timer,
+                previous,
+            } => {
+                let dt_secs = dt.as_secs_f64();
+                match previous.replace(error) {
+                    Some(prev) if dt_secs > 0.0 => {
+                        if ((error - prev) / dt_secs).abs() < *threshold {
+                            *timer += dt;
+                        } else {
+                            *timer = Duration::ZERO;
+                        }
+                    }
+                    _ => {}
+                }
+                *timer >= *time
+            }
+            ExitCondition::Timeout { time, timer } => {
+                *timer += dt;
+                *timer >= *time
+            }
+            // Every child is updated, so their timers stay current even once
+            // the answer is known. `any`/`all` would stop at the first answer.
+            #[allow(clippy::unnecessary_fold)]
+            ExitCondition::Any(conditions) => conditions
+                .iter_mut()
+                .fold(false, |met, c| c.update(error, dt) || met),
+            #[allow(clippy::unnecessary_fold)]
+            ExitCondition::All(conditions) => conditions
+                .iter_mut()
+                .fold(true, |met, c| c.update(error, dt) && met),
+        }
+    }
+
+    /// Clears all timers, ready for the next motion.
+    pub fn reset(&mut self) {
+        match self {
+            ExitCondition::Range { timer, .. } | ExitCondition::Timeout { timer, .. } => {
+                *timer = Duration::ZERO;
+            }
+            ExitCondition::Velocity { timer, previous, .. } => {
+                *timer = Duration::ZERO;
+                *previous = None;
+            }
+            ExitCondition::Any(conditions) | ExitCondition::All(conditions) => {
+                conditions.iter_mut().for_each(ExitCondition::reset);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_millis(10);
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    /// Feeds `error` `n` times, returning whether the last update was met.
+    fn feed(exit: &mut ExitCondition, error: f64, n: usize) -> bool {
+        (0..n).fold(false, |_, _| exit.update(error, TICK))
+    }
+
+    #[test]
+    fn range_needs_to_stay_inside_for_its_time() {
+        let mut exit = ExitCondition::range(1.0, ms(50));
+        assert!(!feed(&mut exit, 0.5, 4));
+        // Leaving the range starts the timer again
+        assert!(!exit.update(2.0, TICK));
+        assert!(!feed(&mut exit, -0.5, 4));
+        assert!(exit.update(-0.5, TICK));
+    }
+
+    #[test]
+    fn velocity_met_once_the_error_stops_changing() {
+        let mut exit = ExitCondition::velocity(10.0, ms(30));
+        // 1 unit per tick is 100 per second, too fast
+        for i in 0..10 {
+            assert!(!exit.update(10.0 - i as f64, TICK));
+        }
+        // The first slow update only has the previous error to compare with
+        assert!(!feed(&mut exit, 5.0, 3));
+        assert!(exit.update(5.0, TICK));
+    }
+
+    #[test]
+    fn timeout_ignores_the_error() {
+        let mut exit = ExitCondition::timeout(ms(50));
+        assert!(!feed(&mut exit, 100.0, 4));
+        assert!(exit.update(0.0, TICK));
+    }
+
+    #[test]
+    fn small_large_falls_back_to_the_large_range() {
+        let mut exit = ExitCondition::small_large(1.0, ms(30), 3.0, ms(100));
+        assert!(!feed(&mut exit, 2.0, 9));
+        assert!(exit.update(2.0, TICK));
+
+        exit.reset();
+        assert!(feed(&mut exit, 0.5, 3));
+    }
+
+    #[test]
+    fn or_and_and_combine() {
+        let mut either = ExitCondition::range(1.0, ms(100)).or(ExitCondition::timeout(ms(30)));
+        assert!(!feed(&mut either, 5.0, 2));
+        assert!(either.update(5.0, TICK));
+
+        let mut both = ExitCondition::range(1.0, ms(20)).and(ExitCondition::timeout(ms(50)));
+        assert!(!feed(&mut both, 0.5, 4));
+        assert!(both.update(0.5, TICK));
+        let mut outside = ExitCondition::range(1.0, ms(20)).and(ExitCondition::timeout(ms(50)));
+        assert!(!feed(&mut outside, 5.0, 10));
+    }
+
+    #[test]
+    fn chaining_flattens() {
+        let exit = ExitCondition::range(1.0, ms(10))
+            .or(ExitCondition::timeout(ms(10)))
+            .or(ExitCondition::velocity(1.0, ms(10)));
+        assert!(matches!(exit, ExitCondition::Any(ref c) if c.len() == 3));
+    }
+
+    #[test]
+    fn any_updates_every_child() {
+        // The timeout is met first, but the range behind it must keep timing
+        let mut exit = ExitCondition::timeout(ms(10)).or(ExitCondition::range(1.0, ms(50)));
+        assert!(feed(&mut exit, 0.5, 5));
+        let ExitCondition::Any(conditions) = &exit else {
+            unreachable!();
+        };
+        assert!(matches!(conditions[1], ExitCondition::Range { timer, .. } if timer == ms(50)));
+    }
+
+    #[test]
+    fn reset_clears_every_timer() {
+        let mut exit = ExitCondition::range(1.0, ms(30))
+            .or(ExitCondition::velocity(10.0, ms(30)))
+            .or(ExitCondition::timeout(ms(30)));
+        assert!(feed(&mut exit, 0.5, 5));
+
+        exit.reset();
+        assert!(!exit.update(0.5, TICK));
+        assert!(!exit.update(0.5, TICK));
+        assert!(exit.update(0.5, TICK));
+    }
+}
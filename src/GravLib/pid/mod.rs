@@ -0,0 +1,412 @@
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::time::Duration;
+
+use crate::GravLib::hardware::{Clock, SystemClock};
+
+pub mod autotune;
+pub mod exit_condition;
+#[cfg(target_vendor = "vex")]
+pub mod tuner;
+
+use exit_condition::ExitCondition;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Gains {
+    pub kP: f32,
+    pub kI: f32,
+    pub kD: f32,
+
+    // feed-forward
+    pub kS: f32,
+    pub kV: f32,
+    pub kA: f32,
+}
+
+impl Gains {
+    /// Feedback gains only; the feed-forward gains start at zero.
+    pub fn new(kP: f32, kI: f32, kD: f32) -> Self {
+        Self {
+            kP,
+            kI,
+            kD,
+            ..Self::default()
+        }
+    }
+}
+
+/// Cloning copies the gains, settings and any state built up so far.
+#[derive(Clone)]
+pub struct PID {
+    m_gains: Gains,
+
+    m_signFlipReset: bool,
+    m_windupRange: f32,
+
+    m_minOutput: f32,
+    m_maxOutput: f32,
+    /// Weight given to the previous derivative, from 0.0 (no filtering) to
+    /// just below 1.0 (heavy filtering).
+    m_derivativeFilter: f32,
+    m_derivativeOnMeasurement: bool,
+
+    m_targetVelocity: f32,
+    m_targetAcceleration: f32,
+
+    m_previousError: Option<f32>,
+    m_previousMeasurement: Option<f32>,
+    m_derivative: f32,
+    m_integral: f32,
+
+    m_exitCondition: Option<ExitCondition>,
+    m_settled: bool,
+
+    m_clock: Arc<dyn Clock>,
+    m_prevTime: Option<Duration>
+}
+
+impl PID {
+    pub fn new(kP: f32, kI: f32, kD: f32, windupRange: f32, signFlipReset: bool) -> Self {
+        Self {
+            m_gains: Gains::new(kP, kI, kD),
+            m_windupRange: windupRange,
+            m_signFlipReset: signFlipReset,
+            m_minOutput: f32::NEG_INFINITY,
+            m_maxOutput: f32::INFINITY,
+            m_derivativeFilter: 0.0,
+            m_derivativeOnMeasurement: false,
+            m_targetVelocity: 0.0,
+            m_targetAcceleration: 0.0,
+            m_previousError: None,
+            m_previousMeasurement: None,
+            m_derivative: 0.0,
+            m_integral: 0.0,
+            m_exitCondition: None,
+            m_settled: false,
+            m_clock: Arc::new(SystemClock::new()),
+            m_prevTime: None,
+        }
+    }
+
+    /// Times `update` with `clock` instead of the Brain's clock, e.g. a
+    /// `sim::ManualClock` when replaying logs on a host.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.m_clock = clock;
+        self
+    }
+
+    /// Adds feed-forward on top of the feedback terms: `kS` to overcome
+    /// static friction, `kV` per unit of target velocity and `kA` per unit of
+    /// target acceleration. The targets are set with `set_target_motion`.
+    pub fn with_feedforward(mut self, kS: f32, kV: f32, kA: f32) -> Self {
+        self.m_gains.kS = kS;
+        self.m_gains.kV = kV;
+        self.m_gains.kA = kA;
+        self
+    }
+
+    /// Clamps the output to `[min, max]`. While the output is clamped the
+    /// integral is wound back so it doesn't keep growing.
+    pub fn with_output_limits(mut self, min: f32, max: f32) -> Self {
+        self.m_minOutput = min;
+        self.m_maxOutput = max;
+        self
+    }
+
+    /// Low-pass filters the derivative. `alpha` is the weight given to the
+    /// previous value, from 0.0 (off) to just below 1.0.
+    pub fn with_derivative_filter(mut self, alpha: f32) -> Self {
+        self.m_derivativeFilter = alpha.clamp(0.0, 0.99);
+        self
+    }
+
+    /// Takes the derivative of the measurement instead of the error, so a
+    /// setpoint change doesn't kick the output. Only takes effect through
+    /// `update_measurement`, since `update` never sees the measurement.
+    pub fn with_derivative_on_measurement(mut self, enabled: bool) -> Self {
+        self.m_derivativeOnMeasurement = enabled;
+        self
+    }
+
+    /// Checks `condition` against the error on every update. See `is_settled`.
+    pub fn with_exit_condition(mut self, condition: ExitCondition) -> Self {
+        self.m_exitCondition = Some(condition);
+        self
+    }
+
+    /// Whether the exit condition was met on the last update. Always `false`
+    /// without one.
+    pub fn is_settled(&self) -> bool {
+        self.m_settled
+    }
+
+    /// Target velocity and acceleration for the feed-forward terms. They
+    /// hold until changed.
+    pub fn set_target_motion(&mut self, velocity: f32, acceleration: f32) {
+        self.m_targetVelocity = velocity;
+        self.m_targetAcceleration = acceleration;
+    }
+
+    pub fn get_gains(&self) -> Gains {
+        self.m_gains
+    }
+
+    /// Swaps the gains without resetting any state, so it is safe to call
+    /// while the controller is running.
+    pub fn set_gains(&mut self, gains: Gains) {
+        self.m_gains = gains;
+    }
+
+    pub fn get_sign_flip_reset(&self) -> bool {
+        self.m_signFlipReset
+    }
+
+    pub fn set_sign_flip_reset(&mut self, signFlipReset: bool) {
+        self.m_signFlipReset = signFlipReset;
+    }
+
+    pub fn set_windup_range(&mut self, windupRange: f32) {
+        self.m_windupRange = windupRange;
+    }
+
+    pub fn get_windup_range(&self) -> f32 {
+        self.m_windupRange
+    }
+
+    /// Timed with the PID's clock. Use `update_with_dt` when the caller
+    /// already knows the time step, so the output doesn't depend on when the
+    /// scheduler happened to run it.
+    pub fn update(&mut self, error: f32) -> f32 {
+        let dt = self.elapsed();
+        self.step(error, None, dt)
+    }
+
+    /// `dt` is the time since the previous update. The same errors and time
+    /// steps always give the same outputs.
+    pub fn update_with_dt(&mut self, error: f32, dt: Duration) -> f32 {
+        self.step(error, None, dt)
+    }
+
+    /// Same as `update`, but from the setpoint and measurement rather than
+    /// their difference, which allows derivative-on-measurement.
+    pub fn update_measurement(&mut self, setpoint: f32, measurement: f32) -> f32 {
+        let dt = self.elapsed();
+        self.step(setpoint - measurement, Some(measurement), dt)
+    }
+
+    pub fn update_measurement_with_dt(&mut self, setpoint: f32, measurement: f32, dt: Duration) -> f32 {
+        self.step(setpoint - measurement, Some(measurement), dt)
+    }
+
+    /// Time since the previous clocked update. Zero on the first.
+    fn elapsed(&mut self) -> Duration {
+        let now = self.m_clock.now();
+        let dt = match self.m_prevTime {
+            Some(prev) => now.saturating_sub(prev),
+            None => Duration::ZERO,
+        };
+        self.m_prevTime = Some(now);
+        dt
+    }
+
+    fn step(&mut self, error: f32, measurement: Option<f32>, dt: Duration) -> f32 {
+        let dt_secs = dt.as_secs_f32();
+
+        // 1. Derivative, either of the error or of the measurement. Nothing
+        // to differentiate against on the first update.
+        let raw_derivative = match (self.m_derivativeOnMeasurement, measurement) {
+            (true, Some(m)) => self.m_previousMeasurement.map(|prev| -(m - prev)),
+            _ => self.m_previousError.map(|prev| error - prev),
+        };
+        let raw_derivative = match raw_derivative {
+            Some(delta) if dt_secs > 0.0 => delta / dt_secs,
+            _ => 0.0,
+        };
+        let alpha = self.m_derivativeFilter;
+        self.m_derivative = alpha * self.m_derivative + (1.0 - alpha) * raw_derivative;
+
+        // 2. Integral (error * time)
+        self.m_integral += error * dt_secs;
+
+        // reset integral on sign flip (if enabled)
+        if let Some(prev) = self.m_previousError {
+            if self.m_signFlipReset && error.signum() != prev.signum() {
+                self.m_integral = 0.0;
+            }
+        }
+
+        // anti windup range. Unless error is small enough, set the integral to 0
+        if error.abs() > self.m_windupRange && self.m_windupRange != 0.0 {
+            self.m_integral = 0.0;
+        }
+
+        self.m_previousError = Some(error);
+        self.m_previousMeasurement = measurement;
+
+        if let Some(exit) = &mut self.m_exitCondition {
+            self.m_settled = exit.update(error as f64, dt);
+        }
+
+        // 3. Feedback and feed-forward
+        let gains = &self.m_gains;
+        let feedforward = gains.kS * sign(self.m_targetVelocity)
+            + gains.kV * self.m_targetVelocity
+            + gains.kA * self.m_targetAcceleration;
+        let output = error * gains.kP
+            + self.m_integral * gains.kI
+            + self.m_derivative * gains.kD
+            + feedforward;
+
+        // 4. Clamp, and back-calculate the integral so its term only holds
+        // the output at the limit rather than winding up past it
+        let clamped = output.clamp(self.m_minOutput, self.m_maxOutput);
+        let excess = output - clamped;
+        let integral_term = self.m_integral * gains.kI;
+        if excess != 0.0 && integral_term != 0.0 && integral_term.signum() == excess.signum() {
+            let unwind = excess.abs().min(integral_term.abs());
+            self.m_integral -= unwind * integral_term.signum() / gains.kI;
+        }
+
+        clamped
+    }
+
+    pub fn reset(&mut self) {
+        self.m_previousError = None;
+        self.m_previousMeasurement = None;
+        self.m_derivative = 0.0;
+        self.m_integral = 0.0;
+        self.m_prevTime = None;
+        self.m_settled = false;
+        if let Some(exit) = &mut self.m_exitCondition {
+            exit.reset();
+        }
+    }
+}
+
+/// Like `signum`, but zero for zero, so `kS` does nothing at rest.
+fn sign(x: f32) -> f32 {
+    if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    use crate::GravLib::sim::ManualClock;
+
+    const DT: Duration = Duration::from_millis(100);
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "expected {expected}, got {actual}");
+    }
+
+    /// A logged error trace with uneven time steps, as a real loop produces.
+    fn logged() -> impl Iterator<Item = (f32, Duration)> {
+        (0..200u64).map(|i| {
+            let error = 30.0 * libm::expf(-(i as f32) / 40.0) * libm::cosf(i as f32 / 7.0);
+            (error, Duration::from_micros(9_000 + (i * 7_919) % 3_000))
+        })
+    }
+
+    fn tuned() -> PID {
+        PID::new(0.8, 0.2, 0.05, 10.0, true)
+            .with_output_limits(-12.0, 12.0)
+            .with_derivative_filter(0.3)
+    }
+
+    #[test]
+    fn replaying_a_log_gives_identical_outputs() {
+        let mut first = tuned();
+        let mut second = tuned();
+        for (error, dt) in logged() {
+            assert_eq!(first.update_with_dt(error, dt).to_bits(), second.update_with_dt(error, dt).to_bits());
+        }
+    }
+
+    #[test]
+    fn update_on_a_manual_clock_matches_update_with_dt() {
+        let clock = ManualClock::new();
+        let mut clocked = tuned().with_clock(Arc::new(clock.clone()));
+        let mut stepped = tuned();
+
+        // The first clocked update has nothing to time against
+        let mut steps = logged();
+        let (error, _) = steps.next().unwrap();
+        assert_eq!(clocked.update(error), stepped.update_with_dt(error, Duration::ZERO));
+
+        for (error, dt) in steps {
+            clock.advance(dt);
+            assert_eq!(clocked.update(error), stepped.update_with_dt(error, dt));
+        }
+    }
+
+    #[test]
+    fn reset_starts_the_replay_over() {
+        let mut pid = tuned();
+        let outputs: Vec<f32> = logged().map(|(e, dt)| pid.update_with_dt(e, dt)).collect();
+        pid.reset();
+        let again: Vec<f32> = logged().map(|(e, dt)| pid.update_with_dt(e, dt)).collect();
+        assert_eq!(outputs, again);
+    }
+
+    #[test]
+    fn feedforward_follows_target_motion() {
+        let mut pid = PID::new(0.0, 0.0, 0.0, 0.0, false).with_feedforward(0.5, 2.0, 0.25);
+
+        pid.set_target_motion(3.0, 4.0);
+        assert_near(pid.update_with_dt(0.0, DT), 0.5 + 6.0 + 1.0);
+
+        pid.set_target_motion(-3.0, 0.0);
+        assert_near(pid.update_with_dt(0.0, DT), -0.5 - 6.0);
+
+        // kS only pushes against friction while moving
+        pid.set_target_motion(0.0, 0.0);
+        assert_near(pid.update_with_dt(0.0, DT), 0.0);
+    }
+
+    #[test]
+    fn clamped_output_does_not_wind_up_the_integral() {
+        let mut pid = PID::new(1.0, 1.0, 0.0, 0.0, false).with_output_limits(-1.0, 1.0);
+        for _ in 0..50 {
+            assert_near(pid.update_with_dt(10.0, DT), 1.0);
+        }
+
+        // Without back-calculation the integral would be 50 by now and hold
+        // the output at the limit long after the error has come down
+        assert_near(pid.update_with_dt(0.5, DT), 0.5 + 0.05);
+    }
+
+    #[test]
+    fn derivative_filter_smooths_steps() {
+        let mut pid = PID::new(0.0, 0.0, 1.0, 0.0, false).with_derivative_filter(0.5);
+        let dt = Duration::from_secs(1);
+        pid.update_with_dt(0.0, dt);
+
+        // A raw derivative of 1, then 0, comes through halved each time
+        assert_near(pid.update_with_dt(1.0, dt), 0.5);
+        assert_near(pid.update_with_dt(1.0, dt), 0.25);
+    }
+
+    #[test]
+    fn derivative_on_measurement_ignores_setpoint_changes() {
+        let dt = Duration::from_secs(1);
+
+        let mut on_error = PID::new(0.0, 0.0, 1.0, 0.0, false);
+        on_error.update_measurement_with_dt(0.0, 0.0, dt);
+        assert_near(on_error.update_measurement_with_dt(10.0, 0.0, dt), 10.0);
+
+        let mut on_measurement = PID::new(0.0, 0.0, 1.0, 0.0, false).with_derivative_on_measurement(true);
+        on_measurement.update_measurement_with_dt(0.0, 0.0, dt);
+        assert_near(on_measurement.update_measurement_with_dt(10.0, 0.0, dt), 0.0);
+        // The measurement moving towards the setpoint still damps
+        assert_near(on_measurement.update_measurement_with_dt(10.0, 1.0, dt), -1.0);
+    }
+}
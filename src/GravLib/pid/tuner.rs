@@ -0,0 +1,232 @@
+// tuner.rs
+//
+// Adjusts PID gains live from the V5 controller, so they can be tuned at the
+// practice field without re-uploading. Controls:
+//
+//   Left / Right   previous / next controller
+//   A              cycle the gain being edited (kP -> kI -> kD)
+//   Up / Down      raise / lower the selected gain by the step
+//   X / B          step x10 / step /10
+//
+// The tuner edits the PIDs themselves, so a change takes effect on the next
+// update of whatever is running them.
+extern crate alloc;
+
+use alloc::{format, string::String, sync::Arc, vec::Vec};
+
+use spin::Mutex;
+use vexide::devices::controller::{ControllerScreen, ControllerState};
+use vexide::devices::math::Point2;
+use vexide::devices::display::*;
+use vexide::prelude::*;
+
+use crate::GravLib::pid::{Gains, PID};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Term {
+    P,
+    I,
+    D,
+}
+
+impl Term {
+    fn next(self) -> Self {
+        match self {
+            Term::P => Term::I,
+            Term::I => Term::D,
+            Term::D => Term::P,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Term::P => "kP",
+            Term::I => "kI",
+            Term::D => "kD",
+        }
+    }
+}
+
+struct Entry {
+    name: &'static str,
+    pid: Arc<Mutex<PID>>,
+}
+
+pub struct PidTuner {
+    entries: Vec<Entry>,
+    selected: usize,
+    term: Term,
+    step: f32,
+    /// Redraw the screens on the next update.
+    dirty: bool,
+    /// What the controller screen should show, and which lines of it still
+    /// have to be sent. The controller takes one line at a time.
+    screen: [String; 3],
+    unsent: [bool; 3],
+}
+
+impl PidTuner {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            selected: 0,
+            term: Term::P,
+            step: 0.1,
+            dirty: true,
+            screen: Default::default(),
+            unsent: [false; 3],
+        }
+    }
+
+    /// Adds a named controller to cycle through. Its kP, kI and kD are
+    /// edited in place; feed-forward gains are left alone.
+    pub fn with(mut self, name: &'static str, pid: &Arc<Mutex<PID>>) -> Self {
+        self.entries.push(Entry { name, pid: Arc::clone(pid) });
+        self
+    }
+
+    /// Current gains for the controller called `name`.
+    pub fn gains(&self, name: &str) -> Option<Gains> {
+        self.entries.iter().find(|e| e.name == name).map(|e| e.pid.lock().get_gains())
+    }
+
+    /// Updates the gains from the buttons in `state`, then redraws the
+    /// controller `screen` and `display` if anything changed. Call it from
+    /// the driver loop with the same `state` the drive reads, since a button
+    /// press only shows up in one `Controller::state` call. Never waits on
+    /// the controller. Returns `true` if a gain changed.
+    pub fn update(&mut self, state: &ControllerState, screen: &mut ControllerScreen, display: &mut Display) -> bool {
+        if self.entries.is_empty() {
+            return false;
+        }
+
+        let count = self.entries.len();
+        let mut changed = false;
+
+        // 1. Navigation
+        if state.button_right.is_now_pressed() {
+            self.selected = (self.selected + 1) % count;
+            self.dirty = true;
+        }
+        if state.button_left.is_now_pressed() {
+            self.selected = (self.selected + count - 1) % count;
+            self.dirty = true;
+        }
+        if state.button_a.is_now_pressed() {
+            self.term = self.term.next();
+            self.dirty = true;
+        }
+        if state.button_x.is_now_pressed() {
+            self.step *= 10.0;
+            self.dirty = true;
+        }
+        if state.button_b.is_now_pressed() {
+            self.step /= 10.0;
+            self.dirty = true;
+        }
+
+        // 2. Adjust the selected gain. Gains can't go negative.
+        let delta = if state.button_up.is_now_pressed() {
+            self.step
+        } else if state.button_down.is_now_pressed() {
+            -self.step
+        } else {
+            0.0
+        };
+        if delta != 0.0 {
+            let mut pid = self.entries[self.selected].pid.lock();
+            let mut gains = pid.get_gains();
+            let gain = match self.term {
+                Term::P => &mut gains.kP,
+                Term::I => &mut gains.kI,
+                Term::D => &mut gains.kD,
+            };
+            *gain = (*gain + delta).max(0.0);
+            pid.set_gains(gains);
+            changed = true;
+            self.dirty = true;
+        }
+
+        // 3. Redraw
+        if self.dirty {
+            self.dirty = false;
+            self.draw_controller();
+            self.draw_display(display);
+        }
+        self.send_line(screen);
+
+        changed
+    }
+
+    fn selected_gain(&self) -> f32 {
+        let gains = self.entries[self.selected].pid.lock().get_gains();
+        match self.term {
+            Term::P => gains.kP,
+            Term::I => gains.kI,
+            Term::D => gains.kD,
+        }
+    }
+
+    /// The controller screen fits 3 lines of 19 characters.
+    fn draw_controller(&mut self) {
+        self.screen = [
+            format!("{:<19}", self.entries[self.selected].name),
+            format!("{:<19}", format!("{}: {:.4}", self.term.label(), self.selected_gain())),
+            format!("{:<19}", format!("step: {}", self.step)),
+        ];
+        self.unsent = [true; 3];
+    }
+
+    /// Sends the next unsent line of the controller screen, if the
+    /// controller is ready for it. A busy controller is retried next update.
+    fn send_line(&mut self, screen: &mut ControllerScreen) {
+        let Some(line) = self.unsent.iter().position(|&unsent| unsent) else {
+            return;
+        };
+        if screen.try_set_text(&self.screen[line], line as u8 + 1, 1).is_ok() {
+            self.unsent[line] = false;
+        }
+    }
+
+    /// Lists every controller's gains, with the one being edited highlighted.
+    fn draw_display(&self, display: &mut Display) {
+        let black = Rgb::new(0, 0, 0);
+
+        display.set_render_mode(RenderMode::DoubleBuffered);
+        display.erase(black);
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let gains = entry.pid.lock().get_gains();
+            let colour = if i == self.selected {
+                Rgb::new(255, 255, 0)
+            } else {
+                Rgb::new(255, 255, 255)
+            };
+            let marker = if i == self.selected { self.term.label() } else { "  " };
+            let text = format!(
+                "{} {:<10} P {:.4}  I {:.4}  D {:.4}",
+                marker, entry.name, gains.kP, gains.kI, gains.kD
+            );
+            display.draw_text(
+                &Text::new(
+                    &text,
+                    Font::new(FontSize::MEDIUM, FontFamily::Monospace),
+                    Point2::<i16>::from([10, 10 + i as i16 * 25]),
+                ),
+                colour,
+                Some(black),
+            );
+        }
+
+        display.draw_text(
+            &Text::new(
+                &format!("step: {}", self.step),
+                Font::new(FontSize::MEDIUM, FontFamily::Monospace),
+                Point2::<i16>::from([10, 210]),
+            ),
+            Rgb::new(255, 255, 255),
+            Some(black),
+        );
+        display.render();
+    }
+}
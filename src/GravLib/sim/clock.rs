@@ -0,0 +1,36 @@
+// clock.rs
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use crate::GravLib::hardware::Clock;
+
+/// A clock that only moves when told to. Clones share the same time.
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    micros: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, dt: Duration) {
+        self.micros.fetch_add(dt.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn set(&self, t: Duration) {
+        self.micros.store(t.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_micros(self.micros.load(Ordering::Relaxed))
+    }
+}
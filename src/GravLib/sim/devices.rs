@@ -0,0 +1,190 @@
+// devices.rs
+//
+// Simulated stand-ins for the vexide devices, backed by a shared
+// `DrivetrainPlant`. They implement the traits in `hardware`, so they plug
+// straight into `MotorGroup`, `TrackingWheel` and `Sensors`.
+extern crate alloc;
+
+use alloc::{boxed::Box, sync::Arc};
+use core::{
+    f64::consts::PI,
+    future::Future,
+    pin::Pin,
+};
+
+use spin::Mutex;
+
+use crate::GravLib::hardware::{Actuator, ActuatorError, BrakeMode, Encoder, Gearset, HeadingSource};
+use crate::GravLib::sim::plant::{DrivetrainPlant, Side};
+
+const METRES_PER_INCH: f64 = 0.0254;
+
+/// V5 motors start limiting power at this temperature, °C.
+const OVER_TEMPERATURE: f64 = 55.0;
+
+pub struct SimMotor {
+    plant: Arc<Mutex<DrivetrainPlant>>,
+    side: Side,
+    index: usize,
+}
+
+impl SimMotor {
+    pub(crate) fn new(plant: Arc<Mutex<DrivetrainPlant>>, side: Side) -> Self {
+        let index = plant.lock().add_motor(side);
+        Self { plant, side, index }
+    }
+}
+
+impl Actuator for SimMotor {
+    fn set_voltage(&mut self, volts: f64) -> Result<(), ActuatorError> {
+        self.plant.lock().set_voltage(self.side, self.index, volts);
+        Ok(())
+    }
+
+    fn set_velocity(&mut self, rpm: i32) -> Result<(), ActuatorError> {
+        self.plant.lock().set_velocity(self.side, self.index, rpm as f64);
+        Ok(())
+    }
+
+    fn brake(&mut self, mode: BrakeMode) -> Result<(), ActuatorError> {
+        self.plant.lock().brake(self.side, self.index, mode);
+        Ok(())
+    }
+
+    fn voltage(&self) -> Result<f64, ActuatorError> {
+        Ok(self.plant.lock().applied_voltage(self.side, self.index))
+    }
+
+    fn position(&self) -> Result<f64, ActuatorError> {
+        Ok(self.plant.lock().motor_position(self.side, self.index).to_degrees())
+    }
+
+    fn reset_position(&mut self) -> Result<(), ActuatorError> {
+        self.plant.lock().reset_motor_position(self.side, self.index);
+        Ok(())
+    }
+
+    fn velocity(&self) -> Result<f64, ActuatorError> {
+        Ok(self.plant.lock().motor_velocity(self.side) * 60.0 / (2.0 * PI))
+    }
+
+    fn current(&self) -> Result<f64, ActuatorError> {
+        Ok(self.plant.lock().motor_current(self.side, self.index))
+    }
+
+    fn temperature(&self) -> Result<f64, ActuatorError> {
+        Ok(self.plant.lock().motor_temperature(self.side, self.index))
+    }
+
+    fn efficiency(&self) -> Result<f64, ActuatorError> {
+        let plant = self.plant.lock();
+        let power_in =
+            plant.applied_voltage(self.side, self.index).abs() * plant.motor_current(self.side, self.index);
+        let power_out = plant.motor_torque(self.side, self.index) * plant.motor_velocity(self.side);
+        if power_in.abs() < f64::EPSILON {
+            return Ok(0.0);
+        }
+        Ok((power_out / power_in * 100.0).clamp(0.0, 100.0))
+    }
+
+    fn faults(&self) -> Result<(), ActuatorError> {
+        let plant = self.plant.lock();
+        if plant.motor_temperature(self.side, self.index) >= OVER_TEMPERATURE {
+            return Err(ActuatorError::OverTemperature);
+        }
+        if plant.motor_current(self.side, self.index) >= plant.stall_current() {
+            return Err(ActuatorError::OverCurrent);
+        }
+        Ok(())
+    }
+
+    fn gearset(&self) -> Result<Gearset, ActuatorError> {
+        Ok(self.plant.lock().config().gearset)
+    }
+}
+
+/// Which way a simulated tracking wheel rolls.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WheelAxis {
+    /// Rolls forward/backward, mounted `offset` inches right of centre.
+    /// Reads positive driving forwards.
+    Vertical,
+    /// Rolls sideways, mounted `offset` inches forward of centre. Reads
+    /// positive sliding right. The same convention as `TrackingWheel::new`.
+    Horizontal,
+}
+
+/// An unpowered tracking wheel riding on the simulated chassis. It measures
+/// the ground under it, so it keeps reading true while the drive wheels slip.
+pub struct SimRotationSensor {
+    plant: Arc<Mutex<DrivetrainPlant>>,
+    axis: WheelAxis,
+    /// m
+    offset: f64,
+    /// m
+    diameter: f64,
+    /// Ground travel (m) that reads as zero.
+    zero: f64,
+}
+
+impl SimRotationSensor {
+    /// `offset` and `diameter` are in inches, like `TrackingWheel`.
+    pub(crate) fn new(
+        plant: Arc<Mutex<DrivetrainPlant>>,
+        axis: WheelAxis,
+        offset: f64,
+        diameter: f64,
+    ) -> Self {
+        let mut sensor = Self {
+            plant,
+            axis,
+            offset: offset * METRES_PER_INCH,
+            diameter: diameter * METRES_PER_INCH,
+            zero: 0.0,
+        };
+        sensor.zero = sensor.ground_travel();
+        sensor
+    }
+
+    /// Distance (m) the ground has moved under the wheel along its axis.
+    fn ground_travel(&self) -> f64 {
+        let (distance, rotation) = self.plant.lock().travel();
+        match self.axis {
+            // Turning clockwise slows points to the right of centre...
+            WheelAxis::Vertical => distance - rotation * self.offset,
+            // ...and sweeps points ahead of centre to the right.
+            WheelAxis::Horizontal => rotation * self.offset,
+        }
+    }
+}
+
+impl Encoder for SimRotationSensor {
+    fn angle(&self) -> Option<f64> {
+        Some((self.ground_travel() - self.zero) / (PI * self.diameter) * 360.0)
+    }
+
+    fn reset(&mut self) {
+        self.zero = self.ground_travel();
+    }
+}
+
+pub struct SimImu {
+    plant: Arc<Mutex<DrivetrainPlant>>,
+}
+
+impl SimImu {
+    pub(crate) fn new(plant: Arc<Mutex<DrivetrainPlant>>) -> Self {
+        Self { plant }
+    }
+}
+
+impl HeadingSource for SimImu {
+    fn heading(&self) -> Option<f64> {
+        let heading = self.plant.lock().heading().to_degrees() % 360.0;
+        Some(if heading < 0.0 { heading + 360.0 } else { heading })
+    }
+
+    fn calibrate(&mut self) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(async {})
+    }
+}
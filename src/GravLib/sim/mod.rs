@@ -0,0 +1,9 @@
+pub mod clock;
+pub mod devices;
+pub mod plant;
+pub mod simulation;
+
+pub use clock::ManualClock;
+pub use devices::{SimImu, SimMotor, SimRotationSensor, WheelAxis};
+pub use plant::{DrivetrainPlant, MotorCurve, Side, SimConfig};
+pub use simulation::Simulation;
@@ -0,0 +1,462 @@
+// plant.rs
+//
+// Rigid-body model of a differential drivetrain. Units are SI throughout
+// (metres, radians, seconds, newtons); heading is clockwise positive from the
+// +y axis to match the IMU.
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{f64::consts::TAU, time::Duration};
+
+use libm::{ceil, cos, fabs, sin};
+
+use crate::GravLib::hardware::{BrakeMode, Gearset};
+
+const GRAVITY: f64 = 9.81;
+const NOMINAL_VOLTAGE: f64 = 12.0;
+
+/// Longest step the integrator takes; longer ticks are split into substeps.
+const MAX_SUBSTEP: f64 = 0.001;
+
+/// Relative wheel/ground speed (m/s) below which a slipping wheel regains grip.
+const SLIP_TOLERANCE: f64 = 0.01;
+
+/// Motor temperature model, °C. Windings heat with current squared and cool
+/// towards ambient; at stall current a motor settles about 30°C above it.
+const AMBIENT_TEMPERATURE: f64 = 25.0;
+/// °C/s per A².
+const HEATING: f64 = 0.1;
+/// 1/s
+const COOLING: f64 = 0.02;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Output shaft characteristics of a V5 motor at 12V with a given cartridge.
+#[derive(Clone, Copy, Debug)]
+pub struct MotorCurve {
+    /// rad/s
+    pub free_speed: f64,
+    /// N·m
+    pub stall_torque: f64,
+    /// A
+    pub stall_current: f64,
+}
+
+impl MotorCurve {
+    pub fn for_gearset(gearset: Gearset) -> Self {
+        let (rpm, stall_torque) = match gearset {
+            Gearset::Red   => (100.0, 2.1),
+            Gearset::Green => (200.0, 1.05),
+            Gearset::Blue  => (600.0, 0.35),
+        };
+
+        Self {
+            free_speed: rpm * TAU / 60.0,
+            stall_torque,
+            stall_current: 2.5,
+        }
+    }
+
+    /// Torque (N·m) and current draw (A) at the output shaft for an applied
+    /// voltage and shaft speed. The motor firmware limits current to the
+    /// stall value, so both saturate there.
+    pub fn evaluate(&self, volts: f64, speed: f64) -> (f64, f64) {
+        let load = (volts / NOMINAL_VOLTAGE - speed / self.free_speed).clamp(-1.0, 1.0);
+        (self.stall_torque * load, self.stall_current * fabs(load))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SimConfig {
+    pub gearset: Gearset,
+    /// Wheel revolutions per motor revolution.
+    pub gear_ratio: f64,
+    /// m
+    pub wheel_diameter: f64,
+    /// m
+    pub track_width: f64,
+    /// kg
+    pub mass: f64,
+    /// kg·m², about the vertical axis.
+    pub moment_of_inertia: f64,
+    /// kg·m², of one side's wheels and gearing, seen at the wheel.
+    pub wheel_inertia: f64,
+    /// Tyre/tile coefficient of friction.
+    pub friction_coefficient: f64,
+    /// N per m/s.
+    pub linear_drag: f64,
+    /// N·m per rad/s.
+    pub angular_drag: f64,
+    /// Open-circuit battery voltage.
+    pub battery_voltage: f64,
+    /// Battery internal resistance in ohms; sets how far it sags under load.
+    pub battery_resistance: f64,
+}
+
+impl Default for SimConfig {
+    /// A 15 lb, 12" wide robot on 3.25" wheels at 450 rpm.
+    fn default() -> Self {
+        Self {
+            gearset: Gearset::Blue,
+            gear_ratio: 36.0 / 48.0,
+            wheel_diameter: 0.0826,
+            track_width: 0.305,
+            mass: 6.8,
+            moment_of_inertia: 0.11,
+            wheel_inertia: 0.002,
+            friction_coefficient: 1.0,
+            linear_drag: 2.0,
+            angular_drag: 0.1,
+            battery_voltage: 12.8,
+            battery_resistance: 0.1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Command {
+    Voltage(f64),
+    /// rad/s at the motor shaft
+    Velocity(f64),
+    Brake(BrakeMode),
+}
+
+struct MotorState {
+    command: Command,
+    /// Shaft angle to hold in `BrakeMode::Hold`.
+    hold_angle: f64,
+    /// Voltage actually applied on the last step.
+    applied_voltage: f64,
+    /// A, on the last step.
+    current: f64,
+    /// N·m at the output shaft, on the last step.
+    torque: f64,
+    /// °C
+    temperature: f64,
+    /// Shaft angle that reads as zero.
+    zero: f64,
+}
+
+impl MotorState {
+    fn new() -> Self {
+        Self {
+            command: Command::Brake(BrakeMode::Coast),
+            hold_angle: 0.0,
+            applied_voltage: 0.0,
+            current: 0.0,
+            torque: 0.0,
+            temperature: AMBIENT_TEMPERATURE,
+            zero: 0.0,
+        }
+    }
+
+    /// Voltage the motor firmware would apply for the current command, or
+    /// `None` if the windings are left open (coasting).
+    fn target_voltage(&self, angle: f64, speed: f64, curve: &MotorCurve) -> Option<f64> {
+        match self.command {
+            Command::Voltage(v) => Some(v),
+            Command::Velocity(target) => {
+                let feedforward = target / curve.free_speed * NOMINAL_VOLTAGE;
+                let kp = 2.0 * NOMINAL_VOLTAGE / curve.free_speed;
+                Some(feedforward + kp * (target - speed))
+            }
+            Command::Brake(BrakeMode::Coast) => None,
+            Command::Brake(BrakeMode::Brake) => Some(0.0),
+            Command::Brake(BrakeMode::Hold) => {
+                Some(40.0 * (self.hold_angle - angle) - 0.5 * speed)
+            }
+        }
+    }
+}
+
+struct SideState {
+    motors: Vec<MotorState>,
+    /// rad/s
+    wheel_speed: f64,
+    /// rad
+    wheel_angle: f64,
+    slipping: bool,
+}
+
+impl SideState {
+    fn new() -> Self {
+        Self {
+            motors: Vec::new(),
+            wheel_speed: 0.0,
+            wheel_angle: 0.0,
+            slipping: false,
+        }
+    }
+}
+
+pub struct DrivetrainPlant {
+    config: SimConfig,
+    curve: MotorCurve,
+
+    left: SideState,
+    right: SideState,
+
+    x: f64,
+    y: f64,
+    heading: f64,
+    /// Forward speed, m/s.
+    velocity: f64,
+    /// Clockwise angular speed, rad/s.
+    angular_velocity: f64,
+
+    /// Integrated forward travel of the robot centre.
+    distance: f64,
+    /// Integrated (unwrapped) rotation.
+    rotation: f64,
+
+    /// Total motor current on the last step, for battery sag.
+    current: f64,
+}
+
+impl DrivetrainPlant {
+    pub fn new(config: SimConfig) -> Self {
+        Self {
+            config,
+            curve: MotorCurve::for_gearset(config.gearset),
+            left: SideState::new(),
+            right: SideState::new(),
+            x: 0.0,
+            y: 0.0,
+            heading: 0.0,
+            velocity: 0.0,
+            angular_velocity: 0.0,
+            distance: 0.0,
+            rotation: 0.0,
+            current: 0.0,
+        }
+    }
+
+    pub fn config(&self) -> &SimConfig {
+        &self.config
+    }
+
+    fn side(&self, side: Side) -> &SideState {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        }
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut SideState {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+        }
+    }
+
+    /// Attaches a new motor to `side` and returns its index on that side.
+    pub fn add_motor(&mut self, side: Side) -> usize {
+        let motors = &mut self.side_mut(side).motors;
+        motors.push(MotorState::new());
+        motors.len() - 1
+    }
+
+    pub fn set_voltage(&mut self, side: Side, motor: usize, volts: f64) {
+        self.side_mut(side).motors[motor].command = Command::Voltage(volts);
+    }
+
+    pub fn set_velocity(&mut self, side: Side, motor: usize, rpm: f64) {
+        self.side_mut(side).motors[motor].command = Command::Velocity(rpm * TAU / 60.0);
+    }
+
+    pub fn brake(&mut self, side: Side, motor: usize, mode: BrakeMode) {
+        let angle = self.motor_shaft_angle(side);
+        let state = &mut self.side_mut(side).motors[motor];
+        state.command = Command::Brake(mode);
+        state.hold_angle = angle;
+    }
+
+    pub fn applied_voltage(&self, side: Side, motor: usize) -> f64 {
+        self.side(side).motors[motor].applied_voltage
+    }
+
+    /// Motor shaft position in radians, relative to its last reset.
+    pub fn motor_position(&self, side: Side, motor: usize) -> f64 {
+        self.motor_shaft_angle(side) - self.side(side).motors[motor].zero
+    }
+
+    pub fn reset_motor_position(&mut self, side: Side, motor: usize) {
+        let angle = self.motor_shaft_angle(side);
+        self.side_mut(side).motors[motor].zero = angle;
+    }
+
+    /// Motor shaft speed in rad/s. Every motor on a side shares it.
+    pub fn motor_velocity(&self, side: Side) -> f64 {
+        self.side(side).wheel_speed / self.config.gear_ratio
+    }
+
+    /// Current draw in amps on the last step.
+    pub fn motor_current(&self, side: Side, motor: usize) -> f64 {
+        self.side(side).motors[motor].current
+    }
+
+    /// Output shaft torque in N·m on the last step.
+    pub fn motor_torque(&self, side: Side, motor: usize) -> f64 {
+        self.side(side).motors[motor].torque
+    }
+
+    /// °C
+    pub fn motor_temperature(&self, side: Side, motor: usize) -> f64 {
+        self.side(side).motors[motor].temperature
+    }
+
+    /// The current limit for this gearset, in amps.
+    pub fn stall_current(&self) -> f64 {
+        self.curve.stall_current
+    }
+
+    fn motor_shaft_angle(&self, side: Side) -> f64 {
+        self.side(side).wheel_angle / self.config.gear_ratio
+    }
+
+    /// Field position in metres.
+    pub fn position(&self) -> (f64, f64) {
+        (self.x, self.y)
+    }
+
+    /// Heading in radians, clockwise positive, unwrapped.
+    pub fn heading(&self) -> f64 {
+        self.heading
+    }
+
+    /// Forward (m/s) and clockwise angular (rad/s) velocity.
+    pub fn velocity(&self) -> (f64, f64) {
+        (self.velocity, self.angular_velocity)
+    }
+
+    /// Forward travel (m) and rotation (rad) integrated since the start.
+    /// Tracking wheels derive their readings from these.
+    pub fn travel(&self) -> (f64, f64) {
+        (self.distance, self.rotation)
+    }
+
+    /// Battery terminal voltage under the present load.
+    pub fn battery_voltage(&self) -> f64 {
+        (self.config.battery_voltage - self.config.battery_resistance * self.current).max(0.0)
+    }
+
+    /// Places the robot at a field position (metres) and heading (radians).
+    pub fn teleport(&mut self, x: f64, y: f64, heading: f64) {
+        self.x = x;
+        self.y = y;
+        self.heading = heading;
+        self.velocity = 0.0;
+        self.angular_velocity = 0.0;
+    }
+
+    /// Advances the model by `dt`.
+    pub fn step(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f64();
+        let substeps = ceil(dt / MAX_SUBSTEP).max(1.0);
+        let h = dt / substeps;
+        for _ in 0..substeps as usize {
+            self.substep(h);
+        }
+    }
+
+    /// Ground speed under each side's wheels, m/s.
+    fn ground_speeds(&self) -> (f64, f64) {
+        let half_track = self.config.track_width / 2.0;
+        (
+            self.velocity + self.angular_velocity * half_track,
+            self.velocity - self.angular_velocity * half_track,
+        )
+    }
+
+    fn substep(&mut self, dt: f64) {
+        let cfg = self.config;
+        let radius = cfg.wheel_diameter / 2.0;
+        let traction = cfg.friction_coefficient * cfg.mass * GRAVITY / 2.0;
+        let limit = self.battery_voltage().min(NOMINAL_VOLTAGE);
+        let (ground_left, ground_right) = self.ground_speeds();
+
+        let mut total_current = 0.0;
+        let mut forces = [0.0; 2];
+
+        for (i, (state, ground)) in [(&mut self.left, ground_left), (&mut self.right, ground_right)]
+            .into_iter()
+            .enumerate()
+        {
+            // 1. Torque delivered to the wheel by every motor on this side
+            let motor_speed = state.wheel_speed / cfg.gear_ratio;
+            let motor_angle = state.wheel_angle / cfg.gear_ratio;
+            let mut wheel_torque = 0.0;
+
+            for motor in state.motors.iter_mut() {
+                let (torque, current) =
+                    match motor.target_voltage(motor_angle, motor_speed, &self.curve) {
+                        Some(volts) => {
+                            let volts = volts.clamp(-limit, limit);
+                            motor.applied_voltage = volts;
+                            self.curve.evaluate(volts, motor_speed)
+                        }
+                        None => {
+                            motor.applied_voltage = 0.0;
+                            (0.0, 0.0)
+                        }
+                    };
+                motor.current = current;
+                motor.torque = torque;
+                motor.temperature += (HEATING * current * current
+                    - COOLING * (motor.temperature - AMBIENT_TEMPERATURE))
+                    * dt;
+
+                wheel_torque += torque / cfg.gear_ratio;
+                total_current += current;
+            }
+
+            // 2. Transfer it to the ground, up to the traction limit
+            let demand = wheel_torque / radius;
+            forces[i] = if !state.slipping && fabs(demand) <= traction {
+                demand
+            } else {
+                let relative = state.wheel_speed * radius - ground;
+                let direction = if relative != 0.0 { relative.signum() } else { demand.signum() };
+                let force = traction * direction;
+                state.wheel_speed += (wheel_torque - force * radius) / cfg.wheel_inertia * dt;
+                state.slipping = true;
+                force
+            };
+        }
+
+        // 3. Integrate the chassis
+        let [left_force, right_force] = forces;
+        let accel = (left_force + right_force - cfg.linear_drag * self.velocity) / cfg.mass;
+        let alpha = ((left_force - right_force) * cfg.track_width / 2.0
+            - cfg.angular_drag * self.angular_velocity)
+            / cfg.moment_of_inertia;
+
+        self.velocity += accel * dt;
+        self.angular_velocity += alpha * dt;
+
+        self.heading += self.angular_velocity * dt;
+        self.x += self.velocity * sin(self.heading) * dt;
+        self.y += self.velocity * cos(self.heading) * dt;
+        self.distance += self.velocity * dt;
+        self.rotation += self.angular_velocity * dt;
+
+        // 4. Gripping wheels follow the ground; slipping ones regain grip
+        //    once they have slowed (or spun up) to match it
+        let (ground_left, ground_right) = self.ground_speeds();
+        for (state, ground) in [(&mut self.left, ground_left), (&mut self.right, ground_right)] {
+            if state.slipping && fabs(state.wheel_speed * radius - ground) < SLIP_TOLERANCE {
+                state.slipping = false;
+            }
+            if !state.slipping {
+                state.wheel_speed = ground / radius;
+            }
+            state.wheel_angle += state.wheel_speed * dt;
+        }
+
+        self.current = total_current;
+    }
+}
@@ -0,0 +1,112 @@
+// simulation.rs
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::time::Duration;
+
+use spin::Mutex;
+
+use crate::GravLib::hardware::Clock;
+use crate::GravLib::sim::{
+    clock::ManualClock,
+    devices::{SimImu, SimMotor, SimRotationSensor, WheelAxis},
+    plant::{DrivetrainPlant, Side, SimConfig},
+};
+
+/// Owns a simulated drivetrain and the clock it runs on. Nothing moves until
+/// `tick` is called, so a run is fully deterministic.
+pub struct Simulation {
+    plant: Arc<Mutex<DrivetrainPlant>>,
+    clock: ManualClock,
+    tick: Duration,
+}
+
+impl Simulation {
+    pub fn new(config: SimConfig, tick: Duration) -> Self {
+        Self {
+            plant: Arc::new(Mutex::new(DrivetrainPlant::new(config))),
+            clock: ManualClock::new(),
+            tick,
+        }
+    }
+
+    /// The simulation's clock. Hand it to `Localisation::with_clock` and
+    /// anything else that needs to agree with simulated time.
+    pub fn clock(&self) -> ManualClock {
+        self.clock.clone()
+    }
+
+    pub fn plant(&self) -> Arc<Mutex<DrivetrainPlant>> {
+        Arc::clone(&self.plant)
+    }
+
+    /// Adds a motor to one side of the drivetrain.
+    pub fn motor(&self, side: Side) -> SimMotor {
+        SimMotor::new(Arc::clone(&self.plant), side)
+    }
+
+    /// Adds a tracking wheel encoder. `offset` and `diameter` are in inches.
+    pub fn rotation_sensor(&self, axis: WheelAxis, offset: f64, diameter: f64) -> SimRotationSensor {
+        SimRotationSensor::new(Arc::clone(&self.plant), axis, offset, diameter)
+    }
+
+    pub fn imu(&self) -> SimImu {
+        SimImu::new(Arc::clone(&self.plant))
+    }
+
+    /// Advances physics and the clock by one tick.
+    pub fn tick(&mut self) {
+        self.plant.lock().step(self.tick);
+        self.clock.advance(self.tick);
+    }
+
+    /// Ticks until at least `duration` of simulated time has passed.
+    pub fn run_for(&mut self, duration: Duration) {
+        let end = self.clock.now() + duration;
+        while self.clock.now() < end {
+            self.tick();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    use crate::GravLib::hardware::{Actuator, HeadingSource};
+    use crate::GravLib::odom::localisation::Localisation;
+    use crate::GravLib::odom::sensors::{Sensors, TrackingWheel};
+
+    const INCHES_PER_METRE: f64 = 1.0 / 0.0254;
+
+    #[test]
+    fn offset_tracking_wheels_agree_with_plant_while_turning() {
+        let mut sim = Simulation::new(SimConfig::default(), Duration::from_millis(10));
+        let vertical = TrackingWheel::new(sim.rotation_sensor(WheelAxis::Vertical, 2.0, 2.75), 2.75, 2.0, 1.0);
+        let horizontal = TrackingWheel::new(sim.rotation_sensor(WheelAxis::Horizontal, -4.5, 2.75), 2.75, -4.5, 1.0);
+        let imu: Arc<Mutex<dyn HeadingSource>> = Arc::new(Mutex::new(sim.imu()));
+        let sensors = Sensors {
+            horizontal_wheels: vec![Arc::new(Mutex::new(horizontal))],
+            vertical_wheels: vec![Arc::new(Mutex::new(vertical))],
+            imu,
+        };
+        let mut localisation = Localisation::with_clock(Arc::new(Mutex::new(sensors)), Arc::new(sim.clock()));
+
+        // Spin clockwise with a little forward drive, so the robot arcs
+        let mut left = sim.motor(Side::Left);
+        let mut right = sim.motor(Side::Right);
+        left.set_voltage(8.0).unwrap();
+        right.set_voltage(-6.0).unwrap();
+        for _ in 0..100 {
+            sim.tick();
+            localisation.update();
+        }
+
+        let (x, y) = sim.plant().lock().position();
+        let (odom_x, odom_y, _) = localisation.pose().get_position();
+        assert!(sim.plant().lock().heading().to_degrees() > 90.0, "barely turned");
+        assert!((odom_x - x * INCHES_PER_METRE).abs() < 0.1, "x: odom {odom_x}, plant {}", x * INCHES_PER_METRE);
+        assert!((odom_y - y * INCHES_PER_METRE).abs() < 0.1, "y: odom {odom_y}, plant {}", y * INCHES_PER_METRE);
+    }
+}
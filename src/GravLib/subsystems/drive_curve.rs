@@ -0,0 +1,113 @@
+// drive_curve.rs
+//
+// Shapes raw stick input before it reaches the drivetrain. Ported from
+// LemLib's `ExpoDriveCurve`, which works on the -127 to 127 stick range; the
+// same `curve` values give the same feel here, but inputs, outputs, deadband
+// and minimum output are all fractions of full stick, -1.0 to 1.0.
+
+/// LemLib's stick range, which `curve` is defined against.
+const STICK_MAX: f64 = 127.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExpoDriveCurve {
+    /// Inputs smaller than this are treated as zero, so a stick that doesn't
+    /// centre perfectly can't creep the robot.
+    pub deadband: f64,
+    /// Smallest output once outside the deadband, enough to overcome
+    /// friction.
+    pub min_output: f64,
+    /// How strongly small inputs are scaled down. 1.0 is linear; LemLib's
+    /// examples use around 1.019.
+    pub curve: f64,
+}
+
+impl Default for ExpoDriveCurve {
+    /// Passes input through unchanged.
+    fn default() -> Self {
+        Self {
+            deadband: 0.0,
+            min_output: 0.0,
+            curve: 1.0,
+        }
+    }
+}
+
+impl ExpoDriveCurve {
+    pub fn new(deadband: f64, min_output: f64, curve: f64) -> Self {
+        Self {
+            deadband,
+            min_output,
+            curve,
+        }
+    }
+
+    /// Maps a stick value to an output, both from -1.0 to 1.0.
+    pub fn apply(&self, input: f64) -> f64 {
+        let input = input.clamp(-1.0, 1.0) * STICK_MAX;
+        let deadband = self.deadband * STICK_MAX;
+        let min_output = self.min_output * STICK_MAX;
+
+        // 1. Deadband
+        if input.abs() <= deadband {
+            return 0.0;
+        }
+
+        // 2. Exponential curve over the range left after the deadband,
+        // normalised so full stick still gives full output
+        let g = input.abs() - deadband;
+        let g_max = STICK_MAX - deadband;
+        let i = libm::pow(self.curve, g - STICK_MAX) * g;
+        let i_max = libm::pow(self.curve, g_max - STICK_MAX) * g_max;
+
+        // 3. Rescale into [min_output, max]
+        let output = (STICK_MAX - min_output) * i / i_max + min_output;
+        libm::copysign(output, input) / STICK_MAX
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_passes_input_through() {
+        let curve = ExpoDriveCurve::default();
+        for input in [-1.0, -0.3, 0.0, 0.25, 1.0] {
+            assert!((curve.apply(input) - input).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn deadband_zeroes_small_input() {
+        let curve = ExpoDriveCurve::new(0.05, 0.1, 1.019);
+        assert_eq!(curve.apply(0.05), 0.0);
+        assert_eq!(curve.apply(-0.04), 0.0);
+        // Just outside jumps straight to the minimum output
+        assert!((curve.apply(0.0501) - 0.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn full_stick_is_full_output_and_odd() {
+        let curve = ExpoDriveCurve::new(0.02, 0.08, 1.019);
+        assert!((curve.apply(1.0) - 1.0).abs() < 1e-12);
+        assert!((curve.apply(-1.0) + 1.0).abs() < 1e-12);
+        // Beyond full stick is clamped
+        assert!((curve.apply(1.5) - 1.0).abs() < 1e-12);
+        for input in [0.1, 0.4, 0.8] {
+            assert_eq!(curve.apply(-input), -curve.apply(input));
+        }
+    }
+
+    #[test]
+    fn curve_scales_small_input_down_and_increases() {
+        let curve = ExpoDriveCurve::new(0.0, 0.0, 1.019);
+        assert!(curve.apply(0.5) < 0.5);
+
+        let mut prev = 0.0;
+        for i in 1..=100 {
+            let output = curve.apply(i as f64 / 100.0);
+            assert!(output > prev);
+            prev = output;
+        }
+    }
+}
@@ -0,0 +1,218 @@
+// driver_assist.rs
+//
+// Keeps the robot pointed where the driver left it. While the turn stick is
+// centred, the heading at that moment is held with a PID, so defense or an
+// uneven drive can't knock the robot off line.
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::time::Duration;
+
+use spin::Mutex;
+
+use crate::GravLib::odom::pose::wrap_degrees;
+use crate::GravLib::PID;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AssistMode {
+    /// Pass the turn input straight through.
+    Off,
+    /// Hold heading whenever the turn stick is centred, even standing still.
+    HeadingHold,
+    /// Hold heading only while driving straight, so the robot doesn't fight
+    /// being pushed while parked.
+    DriftCorrection,
+}
+
+pub struct HeadingAssist {
+    pub mode: AssistMode,
+    /// Takes heading error in degrees, outputs turn as a fraction of full
+    /// power. Shared so it can be tuned while driving.
+    pid: Arc<Mutex<PID>>,
+    /// Largest turn the assist may add, as a fraction of full power.
+    max_correction: f64,
+    /// How long to wait after the turn stick is released before taking the
+    /// heading to hold, so the robot's leftover spin isn't undone.
+    capture_delay: Duration,
+
+    released_for: Duration,
+    target: Option<f64>,
+}
+
+impl HeadingAssist {
+    pub fn new(mode: AssistMode, pid: PID) -> Self {
+        Self {
+            mode,
+            pid: Arc::new(Mutex::new(pid)),
+            max_correction: 0.5,
+            capture_delay: Duration::from_millis(150),
+            released_for: Duration::ZERO,
+            target: None,
+        }
+    }
+
+    pub fn with_max_correction(mut self, max_correction: f64) -> Self {
+        self.max_correction = max_correction;
+        self
+    }
+
+    pub fn with_capture_delay(mut self, delay: Duration) -> Self {
+        self.capture_delay = delay;
+        self
+    }
+
+    /// The heading PID, e.g. to register with a `PidTuner`.
+    pub fn pid(&self) -> Arc<Mutex<PID>> {
+        Arc::clone(&self.pid)
+    }
+
+    /// Heading being held, in degrees, if any.
+    pub fn target(&self) -> Option<f64> {
+        self.target
+    }
+
+    /// Forgets the held heading, e.g. after the pose is reset.
+    pub fn release(&mut self) {
+        self.target = None;
+        self.released_for = Duration::ZERO;
+        self.pid.lock().reset();
+    }
+
+    /// Returns the turn to drive with. `throttle` and `turn` are the shaped
+    /// stick values, `heading` the IMU heading in degrees (if it could be
+    /// read) and `dt` the time since the previous call.
+    pub fn correct(&mut self, throttle: f64, turn: f64, heading: Option<f64>, dt: Duration) -> f64 {
+        let active = match self.mode {
+            AssistMode::Off => false,
+            AssistMode::HeadingHold => true,
+            AssistMode::DriftCorrection => throttle != 0.0,
+        };
+
+        // The driver is turning, or the assist has nothing to do
+        let Some(heading) = heading.filter(|_| active && turn == 0.0) else {
+            if turn != 0.0 || !active {
+                self.release();
+            }
+            return turn;
+        };
+
+        // 1. Wait for the robot to stop spinning, then take its heading
+        let target = match self.target {
+            Some(target) => target,
+            None => {
+                self.released_for += dt;
+                if self.released_for < self.capture_delay {
+                    return turn;
+                }
+                self.target = Some(heading);
+                heading
+            }
+        };
+
+        // 2. Turn back onto it. Clockwise is positive.
+        let error = wrap_degrees(target - heading);
+        let correction = self.pid.lock().update_with_dt(error as f32, dt) as f64;
+        correction.clamp(-self.max_correction, self.max_correction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_millis(50);
+
+    /// Assist with a P-only heading PID and no capture delay unless a test
+    /// asks for one.
+    fn assist(mode: AssistMode) -> HeadingAssist {
+        HeadingAssist::new(mode, PID::new(0.01, 0.0, 0.0, 0.0, false)).with_capture_delay(Duration::ZERO)
+    }
+
+    #[test]
+    fn waits_out_the_capture_delay_before_holding() {
+        let mut assist = assist(AssistMode::HeadingHold).with_capture_delay(Duration::from_millis(150));
+
+        // Still spinning down after the stick is released
+        for heading in [10.0, 14.0] {
+            assert_eq!(assist.correct(0.0, 0.0, Some(heading), TICK), 0.0);
+            assert_eq!(assist.target(), None);
+        }
+        assert_eq!(assist.correct(0.0, 0.0, Some(16.0), TICK), 0.0);
+        assert_eq!(assist.target(), Some(16.0));
+    }
+
+    #[test]
+    fn turns_back_against_drift_either_way() {
+        let mut assist = assist(AssistMode::HeadingHold);
+        assist.correct(0.0, 0.0, Some(90.0), TICK);
+
+        // Knocked clockwise, so turn anticlockwise
+        assert!((assist.correct(0.0, 0.0, Some(100.0), TICK) + 0.1).abs() < 1e-6);
+        // Knocked anticlockwise, so turn clockwise
+        assert!((assist.correct(0.0, 0.0, Some(80.0), TICK) - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn takes_the_short_way_across_north() {
+        let mut assist = assist(AssistMode::HeadingHold);
+        assist.correct(0.0, 0.0, Some(355.0), TICK);
+        // 10° clockwise of the target, across 0
+        assert!((assist.correct(0.0, 0.0, Some(5.0), TICK) + 0.1).abs() < 1e-6);
+
+        assist.release();
+        assist.correct(0.0, 0.0, Some(5.0), TICK);
+        assert!((assist.correct(0.0, 0.0, Some(355.0), TICK) - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn driver_turning_releases_the_heading() {
+        let mut assist = assist(AssistMode::HeadingHold);
+        assist.correct(0.0, 0.0, Some(90.0), TICK);
+
+        assert_eq!(assist.correct(0.5, 0.3, Some(100.0), TICK), 0.3);
+        assert_eq!(assist.target(), None);
+
+        // Stick centred again, so the new heading is the one held
+        assert_eq!(assist.correct(0.5, 0.0, Some(120.0), TICK), 0.0);
+        assert_eq!(assist.target(), Some(120.0));
+    }
+
+    #[test]
+    fn drift_correction_leaves_a_parked_robot_alone() {
+        let mut assist = assist(AssistMode::DriftCorrection);
+        assist.correct(0.5, 0.0, Some(90.0), TICK);
+        assert!(assist.correct(0.5, 0.0, Some(100.0), TICK) < 0.0);
+
+        assert_eq!(assist.correct(0.0, 0.0, Some(110.0), TICK), 0.0);
+        assert_eq!(assist.target(), None);
+    }
+
+    #[test]
+    fn off_passes_the_turn_through() {
+        let mut assist = assist(AssistMode::Off);
+        assert_eq!(assist.correct(0.5, 0.0, Some(90.0), TICK), 0.0);
+        assert_eq!(assist.correct(0.5, -0.2, Some(100.0), TICK), -0.2);
+        assert_eq!(assist.target(), None);
+    }
+
+    #[test]
+    fn unreadable_imu_passes_the_turn_through() {
+        let mut assist = assist(AssistMode::HeadingHold);
+        assist.correct(0.0, 0.0, Some(90.0), TICK);
+
+        assert_eq!(assist.correct(0.5, 0.0, None, TICK), 0.0);
+        // The held heading survives the dropout
+        assert_eq!(assist.target(), Some(90.0));
+        assert!(assist.correct(0.5, 0.0, Some(100.0), TICK) < 0.0);
+    }
+
+    #[test]
+    fn correction_is_clamped() {
+        let mut assist = assist(AssistMode::HeadingHold).with_max_correction(0.2);
+        assist.correct(0.0, 0.0, Some(0.0), TICK);
+
+        // 90° off would ask for 0.9
+        assert_eq!(assist.correct(0.0, 0.0, Some(270.0), TICK), 0.2);
+        assert_eq!(assist.correct(0.0, 0.0, Some(90.0), TICK), -0.2);
+    }
+}
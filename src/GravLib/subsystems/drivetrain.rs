@@ -1,25 +1,277 @@
-use alloc::{sync::Arc, vec::Vec};
-use core::{f64::consts::TAU, ops::Add, time::Duration};
+extern crate alloc;
 
-use vexide::{
-    core::{sync::Mutex, time::Instant},
-};
+use alloc::sync::Arc;
+use core::{f64::consts::PI, time::Duration};
 
-use crate::{
-    actuator::MotorGroup,
-};
+use spin::Mutex;
+#[cfg(target_vendor = "vex")]
+use vexide::devices::controller::ControllerState;
 
-#[allow(dead_code)]
+use crate::GravLib::actuator::{MotorGroup, MotorGroupError};
+use crate::GravLib::hardware::{BrakeMode, Clock, HeadingSource, SystemClock};
+use crate::GravLib::motions::util::desaturate;
+#[cfg(target_vendor = "vex")]
+use crate::GravLib::motions::Chassis;
+#[cfg(target_vendor = "vex")]
+use crate::GravLib::odom::localisation::Localisation;
+use crate::GravLib::subsystems::{drive_curve::ExpoDriveCurve, driver_assist::HeadingAssist};
+
+/// Below this much throttle, curvature drive blends towards turning in place
+/// like arcade, which it does fully at zero throttle.
+const CURVATURE_BLEND: f64 = 0.2;
+
+/// Which sticks drive the robot in `DriveTrain::drive`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DriveMode {
+    /// Left stick Y drives the left side, right stick Y the right side.
+    Tank,
+    /// Left stick Y is throttle, left stick X is turn.
+    SingleArcade,
+    /// Left stick Y is throttle, right stick X is turn.
+    SplitArcade,
+    /// Left stick Y is throttle, right stick X sets the curvature of the arc.
+    Curvature,
+}
+
+/// Both sides of a differential drive and its geometry. The motor groups are
+/// shared, so a `Chassis` for autonomous motions can be made from the same
+/// motors with `chassis`.
 pub struct DriveTrain {
-    left: Arc<Mutex<MotorGroup>>,
-    right: Arc<Mutex<MotorGroup>>,
-    _odom_task: Task<()>,
+    pub left: Arc<Mutex<MotorGroup>>,
+    pub right: Arc<Mutex<MotorGroup>>,
+    /// Distance between the left and right wheels, in inches.
+    track_width: f64,
+    /// In inches.
+    wheel_diameter: f64,
+    /// Wheel speed at full power, after gearing.
+    wheel_rpm: f64,
+    throttle_curve: ExpoDriveCurve,
+    turn_curve: ExpoDriveCurve,
+    assist: Option<(HeadingAssist, Arc<Mutex<dyn HeadingSource>>)>,
+    clock: Arc<dyn Clock>,
+    last_assist: Option<Duration>,
 }
 
 impl DriveTrain {
-    pub async fn new(
-        left_motors: Arc<Mutex<Vec<MotorGroup>>>,
-        right_motors: Arc<Mutex<Vec<MotorGroup>>>,
-        imu: InertialSensor
+    pub fn new(
+        left: MotorGroup,
+        right: MotorGroup,
+        track_width: f64,
+        wheel_diameter: f64,
+        wheel_rpm: f64,
+    ) -> Self {
+        Self {
+            left: Arc::new(Mutex::new(left.with_name("left drive"))),
+            right: Arc::new(Mutex::new(right.with_name("right drive"))),
+            track_width,
+            wheel_diameter,
+            wheel_rpm,
+            throttle_curve: ExpoDriveCurve::default(),
+            turn_curve: ExpoDriveCurve::default(),
+            assist: None,
+            clock: Arc::new(SystemClock::new()),
+            last_assist: None,
+        }
+    }
+
+    /// Shapes stick input for the driver control methods. `throttle` also
+    /// applies to both sticks in tank drive.
+    pub fn with_curves(mut self, throttle: ExpoDriveCurve, turn: ExpoDriveCurve) -> Self {
+        self.throttle_curve = throttle;
+        self.turn_curve = turn;
+        self
+    }
+
+    /// Corrects the turn input in arcade and curvature drive with `assist`,
+    /// reading heading from `imu`.
+    pub fn with_assist(mut self, assist: HeadingAssist, imu: Arc<Mutex<dyn HeadingSource>>) -> Self {
+        self.assist = Some((assist, imu));
+        self
+    }
+
+    pub fn assist(&mut self) -> Option<&mut HeadingAssist> {
+        self.assist.as_mut().map(|(assist, _)| assist)
+    }
+
+    pub fn track_width(&self) -> f64 {
+        self.track_width
+    }
+
+    pub fn wheel_diameter(&self) -> f64 {
+        self.wheel_diameter
+    }
+
+    pub fn wheel_rpm(&self) -> f64 {
+        self.wheel_rpm
+    }
+
+    /// Top speed of each side, in inches per second.
+    pub fn max_speed(&self) -> f64 {
+        self.wheel_rpm / 60.0 * PI * self.wheel_diameter
+    }
+
+    /// A `Chassis` driving these motors, for running motions.
+    #[cfg(target_vendor = "vex")]
+    pub fn chassis(&self, localisation: Arc<Mutex<Localisation>>) -> Chassis {
+        Chassis::new(Arc::clone(&self.left), Arc::clone(&self.right), localisation)
+    }
+
+    // Driver control runs these every tick and can't do anything about a
+    // failed motor, so they don't return failures. The motor groups log each
+    // motor that starts failing, and `check` reports which are unhealthy.
+
+    /// Drives each side at a fraction of full power, from -1.0 to 1.0.
+    /// Healthy motors keep driving if one fails.
+    pub fn drive_sides(&self, left: f64, right: f64) {
+        let _ = self.left.lock().move_voltage(left.clamp(-1.0, 1.0) * 12.0);
+        let _ = self.right.lock().move_voltage(right.clamp(-1.0, 1.0) * 12.0);
+    }
+
+    pub fn brake(&self, mode: BrakeMode) {
+        let _ = self.left.lock().brake(mode);
+        let _ = self.right.lock().brake(mode);
+    }
+
+    /// `MotorGroup::check` for the left and right sides.
+    pub fn check(&self) -> (Result<(), MotorGroupError>, Result<(), MotorGroupError>) {
+        (self.left.lock().check(), self.right.lock().check())
+    }
+
+    /// Reads the sticks for `mode` from `state` and drives with them.
+    #[cfg(target_vendor = "vex")]
+    pub fn drive(&mut self, mode: DriveMode, state: &ControllerState) {
+        let (left_x, left_y) = (state.left_stick.x(), state.left_stick.y());
+        let (right_x, right_y) = (state.right_stick.x(), state.right_stick.y());
+
+        match mode {
+            DriveMode::Tank => self.tank(left_y, right_y),
+            DriveMode::SingleArcade => self.arcade(left_y, left_x),
+            DriveMode::SplitArcade => self.split_arcade(left_y, right_x),
+            DriveMode::Curvature => self.curvature(left_y, right_x),
+        }
+    }
+
+    // The driver control methods below take raw stick values and shape them
+    // with the drive curves first.
+
+    /// Each stick drives its own side.
+    pub fn tank(&self, left: f64, right: f64) {
+        self.drive_sides(self.throttle_curve.apply(left), self.throttle_curve.apply(right));
+    }
+
+    /// Throttle and turn from one stick. Turn is positive clockwise.
+    pub fn arcade(&mut self, throttle: f64, turn: f64) {
+        let throttle = self.throttle_curve.apply(throttle);
+        let turn = self.assisted_turn(throttle, self.turn_curve.apply(turn));
+        let (left, right) = arcade_powers(throttle, turn);
+        self.drive_sides(left, right);
+    }
+
+    /// Throttle and turn from separate sticks. The mixing is the same as
+    /// `arcade`; only which sticks feed it differs.
+    pub fn split_arcade(&mut self, throttle: f64, turn: f64) {
+        self.arcade(throttle, turn);
+    }
+
+    /// "Cheesy" drive: the turn stick sets how tightly the robot arcs rather
+    /// than how fast it spins, so turning feels the same at any speed. With
+    /// no throttle it turns in place like arcade.
+    pub fn curvature(&mut self, throttle: f64, turn: f64) {
+        let throttle = self.throttle_curve.apply(throttle);
+        let turn = self.assisted_turn(throttle, self.turn_curve.apply(turn));
+        let (left, right) = curvature_powers(throttle, turn);
+        self.drive_sides(left, right);
+    }
+
+    /// `turn` with the heading assist's correction, if there is one.
+    fn assisted_turn(&mut self, throttle: f64, turn: f64) -> f64 {
+        let Some((assist, imu)) = &mut self.assist else {
+            return turn;
+        };
+
+        let now = self.clock.now();
+        let dt = self.last_assist.map_or(Duration::ZERO, |last| now.saturating_sub(last));
+        self.last_assist = Some(now);
+
+        let heading = imu.lock().heading();
+        assist.correct(throttle, turn, heading, dt)
+    }
+}
+
+/// Left and right power for arcade drive. If throttle and turn together
+/// would exceed full power, both are scaled down so the turn ratio holds
+/// instead of one side clipping.
+pub fn arcade_powers(throttle: f64, turn: f64) -> (f64, f64) {
+    desaturate(throttle, turn, 1.0)
+}
+
+/// Left and right power for curvature drive. Both sides are scaled down
+/// together if either would exceed full power, so the arc holds.
+///
+/// Near zero throttle the arc is mixed with arcade's turn in place, so the
+/// turn rate changes smoothly as the throttle stick passes through centre.
+pub fn curvature_powers(throttle: f64, turn: f64) -> (f64, f64) {
+    let left = throttle + throttle.abs() * turn;
+    let right = throttle - throttle.abs() * turn;
+    let scale = left.abs().max(right.abs()).max(1.0);
+    let (left, right) = (left / scale, right / scale);
+
+    // 1. Blend in turning in place at low throttle
+    let blend = 1.0 - throttle.abs() / CURVATURE_BLEND;
+    if blend <= 0.0 {
+        return (left, right);
+    }
+    let (arcade_left, arcade_right) = arcade_powers(throttle, turn);
+    (
+        left + (arcade_left - left) * blend,
+        right + (arcade_right - right) * blend,
     )
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+    }
+
+    #[test]
+    fn curvature_turns_in_place_without_throttle() {
+        assert!(close(curvature_powers(0.0, 0.5), (0.5, -0.5)));
+        assert!(close(curvature_powers(0.0, -1.0), (-1.0, 1.0)));
+    }
+
+    #[test]
+    fn curvature_is_a_pure_arc_past_the_blend() {
+        // The inner side runs at (1 - turn) of the outer
+        let (left, right) = curvature_powers(0.5, 0.5);
+        assert!(close((left, right), (0.75, 0.25)));
+        assert!(close(curvature_powers(CURVATURE_BLEND, 0.5), (0.3, 0.1)));
+    }
+
+    #[test]
+    fn curvature_turn_rate_changes_smoothly_through_centre() {
+        // No step in turn rate anywhere on the throttle stick's travel
+        let turn = |throttle: f64| {
+            let (left, right) = curvature_powers(throttle, 0.8);
+            left - right
+        };
+        let mut prev = turn(-1.0);
+        for i in -999..=1000 {
+            let rate = turn(i as f64 / 1000.0);
+            assert!((rate - prev).abs() < 0.02, "jump at throttle {}: {prev} -> {rate}", i as f64 / 1000.0);
+            prev = rate;
+        }
+    }
+
+    #[test]
+    fn curvature_never_exceeds_full_power() {
+        for throttle in [-1.0, -0.3, -0.05, 0.0, 0.05, 0.3, 1.0] {
+            for turn in [-1.0, -0.5, 0.5, 1.0] {
+                let (left, right) = curvature_powers(throttle, turn);
+                assert!(left.abs() <= 1.0 && right.abs() <= 1.0, "{throttle}, {turn}: {left}, {right}");
+            }
+        }
+    }
+}
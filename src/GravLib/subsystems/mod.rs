@@ -0,0 +1,7 @@
+pub mod drive_curve;
+pub mod drivetrain;
+pub mod driver_assist;
+
+pub use drive_curve::ExpoDriveCurve;
+pub use drivetrain::{DriveMode, DriveTrain};
+pub use driver_assist::{AssistMode, HeadingAssist};
@@ -0,0 +1,89 @@
+// testing.rs
+//
+// Scripted stand-ins for the sensors, for host tests that replay recorded
+// wheel and IMU traces.
+extern crate alloc;
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{future::Future, pin::Pin, time::Duration};
+
+use crate::GravLib::hardware::{Clock, Encoder, HeadingSource};
+use crate::GravLib::sim::ManualClock;
+
+/// A recorded series of `(timestamp, value)` samples, sorted by timestamp.
+/// Reading it at time `t` yields the latest sample at or before `t`.
+struct Trace {
+    samples: Vec<(Duration, f64)>,
+}
+
+impl Trace {
+    fn new(mut samples: Vec<(Duration, f64)>) -> Self {
+        samples.sort_by_key(|(t, _)| *t);
+        Self { samples }
+    }
+
+    fn at(&self, t: Duration) -> Option<f64> {
+        let idx = self.samples.partition_point(|(ts, _)| *ts <= t);
+        if idx == 0 {
+            None
+        } else {
+            Some(self.samples[idx - 1].1)
+        }
+    }
+}
+
+/// Replays a recorded wheel angle trace (degrees) against a shared clock.
+/// Reads as disconnected before the first sample.
+pub struct ScriptedEncoder {
+    trace: Trace,
+    clock: ManualClock,
+    zero: f64,
+}
+
+impl ScriptedEncoder {
+    pub fn new(samples: Vec<(Duration, f64)>, clock: ManualClock) -> Self {
+        Self {
+            trace: Trace::new(samples),
+            clock,
+            zero: 0.0,
+        }
+    }
+}
+
+impl Encoder for ScriptedEncoder {
+    fn angle(&self) -> Option<f64> {
+        self.trace.at(self.clock.now()).map(|a| a - self.zero)
+    }
+
+    fn reset(&mut self) {
+        self.zero = self.trace.at(self.clock.now()).unwrap_or(0.0);
+    }
+}
+
+/// Replays a recorded IMU heading trace (degrees) against a shared clock.
+pub struct ScriptedHeading {
+    trace: Trace,
+    clock: ManualClock,
+}
+
+impl ScriptedHeading {
+    pub fn new(samples: Vec<(Duration, f64)>, clock: ManualClock) -> Self {
+        Self {
+            trace: Trace::new(samples),
+            clock,
+        }
+    }
+}
+
+impl HeadingSource for ScriptedHeading {
+    fn heading(&self) -> Option<f64> {
+        self.trace.at(self.clock.now()).map(|h| {
+            let h = h % 360.0;
+            if h < 0.0 { h + 360.0 } else { h }
+        })
+    }
+
+    fn calibrate(&mut self) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(async {})
+    }
+}
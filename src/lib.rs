@@ -0,0 +1,12 @@
+// GravLib as a library, so the same code can be built into the robot program
+// (`main.rs`) and tested on a host. Only the V5 target is `no_std`; anything
+// that needs a vexide device is left out of host builds.
+#![cfg_attr(target_vendor = "vex", no_std)]
+// The library keeps the `m_` fields and `kP`-style gains it grew up with
+#![allow(non_snake_case)]
+// vexide runs everything on one thread, so shared devices are `Arc<Mutex<_>>`
+// even though most of them aren't `Send`
+#![allow(clippy::arc_with_non_send_sync)]
+extern crate alloc;
+
+pub mod GravLib;
@@ -1,7 +1,6 @@
 #![no_main]
 #![no_std]
 extern crate alloc;
-mod GravLib;
 
 use core::time::Duration;
 
@@ -13,48 +12,131 @@ use vexide::devices::math::Point2;
 use vexide::prelude::*;
 use vexide::devices::{display::*};
 
-use crate::GravLib::misc::gravlib_logo;
-use crate::GravLib::odom::{
+use gravity::GravLib::actuator::MotorGroup;
+use gravity::GravLib::misc::gravlib_logo;
+use gravity::GravLib::motions::{move_to_point::MoveToSettings, Chassis};
+use gravity::GravLib::subsystems::{AssistMode, DriveMode, DriveTrain, ExpoDriveCurve, HeadingAssist};
+use gravity::GravLib::{ExitCondition, PID};
+use gravity::GravLib::hardware::HeadingSource;
+use gravity::GravLib::pid::tuner::PidTuner;
+use gravity::GravLib::odom::{
     sensors::{TrackingWheel, Sensors},
-    localisation::Localisation
+    localisation::Localisation,
+    field::StartTile,
+    task::OdometryTask,
 };
 
+/// Set to `true` to adjust PID gains from the controller during driver
+/// control. The Brain screen shows the gains instead of the pose.
+const TUNING: bool = false;
+
+/// PLACEHOLDER: How the driver's sticks control the drivetrain.
+const DRIVE_MODE: DriveMode = DriveMode::SplitArcade;
+
 struct Robot {
     controller: Controller,
     display: Arc<Mutex<Display>>,
+    drivetrain: DriveTrain,
+    /// Runs autonomous motions on the drivetrain's motors.
+    #[allow(dead_code)]
+    chassis: Chassis,
     localisation: Arc<Mutex<Localisation>>,
+    odometry: OdometryTask,
+    /// Shared with the tuner, so motions started after a change use the
+    /// tuned gains.
+    lateral_pid: Arc<Mutex<PID>>,
+    angular_pid: Arc<Mutex<PID>>,
+    tuner: PidTuner,
 }
 
 impl Robot {
     pub fn new(peripherals: Peripherals) -> Self {
+        // Drivetrain configuration placeholders
+        let mut drivetrain = DriveTrain::new(
+            MotorGroup::new(vec![
+                Motor::new(peripherals.port_1, Gearset::Blue, Direction::Reverse), // PLACEHOLDER: Configure left motor ports
+                Motor::new(peripherals.port_2, Gearset::Blue, Direction::Reverse),
+                Motor::new(peripherals.port_3, Gearset::Blue, Direction::Reverse),
+            ]),
+            MotorGroup::new(vec![
+                Motor::new(peripherals.port_4, Gearset::Blue, Direction::Forward), // PLACEHOLDER: Configure right motor ports
+                Motor::new(peripherals.port_5, Gearset::Blue, Direction::Forward),
+                Motor::new(peripherals.port_6, Gearset::Blue, Direction::Forward),
+            ]),
+            12.0, // PLACEHOLDER: Set track width in inches
+            3.25, // PLACEHOLDER: Set wheel diameter in inches
+            450.0, // PLACEHOLDER: Set wheel rpm after gearing
+        )
+        .with_curves(
+            ExpoDriveCurve::new(0.02, 0.08, 1.019), // PLACEHOLDER: Tune throttle deadband, min output and curve
+            ExpoDriveCurve::new(0.02, 0.08, 1.019), // PLACEHOLDER: Tune turn deadband, min output and curve
+        );
+
         // Sensor configuration placeholders
         let vertical_wheel = Arc::new(Mutex::new(TrackingWheel::new(
             RotationSensor::new(peripherals.port_10, Direction::Forward), // PLACEHOLDER: Configure vertical tracking wheel port
             2.75, // PLACEHOLDER: Set wheel diameter in inches
-            0.0, // PLACEHOLDER: Set vertical offset from center in inches
+            0.0, // PLACEHOLDER: Set vertical offset from center in inches (right is positive)
             1.0, // PLACEHOLDER: Set gear ratio
         )));
         
         let horizontal_wheel = Arc::new(Mutex::new(TrackingWheel::new(
             RotationSensor::new(peripherals.port_9, Direction::Forward), // PLACEHOLDER: Configure horizontal tracking wheel port
             2.75, // PLACEHOLDER: Set wheel diameter in inches 
-            -4.5, // PLACEHOLDER: Set horizontal offset from center in inches
+            -4.5, // PLACEHOLDER: Set horizontal offset from center in inches (forwards is positive)
             1.0, // PLACEHOLDER: Set gear ratio
         )));
 
+        let imu: Arc<Mutex<dyn HeadingSource>> =
+            Arc::new(Mutex::new(InertialSensor::new(peripherals.port_7))); // PLACEHOLDER: Configure IMU port
+
+        // Hold heading while driving straight, so defense can't knock the robot off line
+        let assist = HeadingAssist::new(AssistMode::DriftCorrection, PID::new(0.02, 0.0, 0.0, 0.0, false)); // PLACEHOLDER: Tune heading PID
+        let heading_pid = assist.pid();
+        drivetrain = drivetrain.with_assist(assist, Arc::clone(&imu));
+
         let sensors = Arc::new(Mutex::new(Sensors {
             horizontal_wheels: vec![horizontal_wheel],
             vertical_wheels: vec![vertical_wheel],
-            imu: Arc::new(Mutex::new(InertialSensor::new(peripherals.port_7))), // PLACEHOLDER: Configure IMU port
+            imu,
         }));
 
         let localisation = Arc::new(Mutex::new(Localisation::new(sensors)));
+        let chassis = drivetrain.chassis(Arc::clone(&localisation));
         let display = Arc::new(Mutex::new(peripherals.display));
+        let mut odometry = OdometryTask::new(Arc::clone(&localisation), Duration::from_millis(10));
+        if !TUNING {
+            odometry = odometry.with_display(Arc::clone(&display));
+        }
+
+        let lateral_pid = Arc::new(Mutex::new(PID::new(0.1, 0.0, 0.0, 0.0, false))); // PLACEHOLDER: Tune lateral PID
+        let angular_pid = Arc::new(Mutex::new(PID::new(0.02, 0.0, 0.0, 0.0, false))); // PLACEHOLDER: Tune angular PID
+        let tuner = PidTuner::new()
+            .with("lateral", &lateral_pid)
+            .with("angular", &angular_pid)
+            .with("heading", &heading_pid);
 
         Self {
             controller: peripherals.primary_controller,
             display,
+            drivetrain,
+            chassis,
             localisation,
+            odometry,
+            lateral_pid,
+            angular_pid,
+            tuner,
+        }
+    }
+
+    /// Settings for `move_to_point`, with the current lateral and angular
+    /// gains.
+    #[allow(dead_code)]
+    fn move_settings(&self) -> MoveToSettings {
+        MoveToSettings {
+            lateral_pid: self.lateral_pid.lock().clone(),
+            angular_pid: self.angular_pid.lock().clone(),
+            exit: ExitCondition::range(1.0, Duration::from_millis(100)), // PLACEHOLDER: Tune exit range and time
         }
     }
 
@@ -71,29 +153,27 @@ impl Robot {
         self.localisation.lock().calibrate(true).await;
 
         println!("Robot calibration complete.");
-        
 
-        // 3. Spawn a background task for continual localisation updates & telemetry
-        let loc = Arc::clone(&self.localisation);
-        let disp = Arc::clone(&self.display);
+        // 3. Report any drive motor that is unplugged or faulted
+        let (left, right) = self.drivetrain.check();
+        if let Err(e) = left {
+            println!("Left drive: {}", e);
+        }
+        if let Err(e) = right {
+            println!("Right drive: {}", e);
+        }
 
-        vexide::task::spawn(async move {
-            loop {
-                {
-                    let mut local = loc.lock();
-                    let mut d = disp.lock();
-                    local.update(&mut *d);
-                }
-                // sleep between updates
-                let _ = vexide::time::sleep(Duration::from_millis(10)).await;
-            }
-        }).detach();
+        // 4. Start the background task for continual localisation updates & telemetry
+        self.odometry.start();
     }
 }
 
 
 impl Compete for Robot {
     async fn autonomous(&mut self) {
+        // PLACEHOLDER: Set the tile the robot starts on
+        self.localisation.lock().set_pose(StartTile::RedLeft.pose());
+
         // your autonomous code here
         println!("Autonomous!");
     }
@@ -111,6 +191,15 @@ impl Compete for Robot {
         //     vexide::time::sleep(Duration::from_millis(10)).await;
         // }
         loop {
+            // Read once per tick: a button press only shows up in one read
+            if let Ok(state) = self.controller.state() {
+                self.drivetrain.drive(DRIVE_MODE, &state);
+
+                if TUNING {
+                    self.tuner.update(&state, &mut self.controller.screen, &mut self.display.lock());
+                }
+            }
+
             vexide::time::sleep(Duration::from_millis(10)).await;
         }
     }